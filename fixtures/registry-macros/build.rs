@@ -0,0 +1 @@
+delegate_trait::build_delegate_impl!("./INPUT");
@@ -0,0 +1,3 @@
+//! Single-crate registry delegating the traits of `delegate-trait-fixture`.
+
+include!(concat!(env!("OUT_DIR"), "/lib.rs"));
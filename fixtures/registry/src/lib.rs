@@ -0,0 +1,181 @@
+//! Traits registered in `delegate-trait-fixture-macros`, and the types the integration tests
+//! delegate them to.
+
+use std::collections::HashMap;
+
+pub trait Store {
+    fn get(&self, key: &str) -> Option<u32>;
+    fn set(&mut self, key: &str, value: u32);
+}
+
+#[derive(Default)]
+pub struct Mem(pub HashMap<String, u32>);
+
+impl Store for Mem {
+    fn get(&self, key: &str) -> Option<u32> {
+        self.0.get(key).copied()
+    }
+
+    fn set(&mut self, key: &str, value: u32) {
+        self.0.insert(key.to_string(), value);
+    }
+}
+
+pub mod types {
+    #[derive(Debug, Clone, PartialEq)]
+    pub struct Key(pub String);
+
+    impl From<&str> for Key {
+        fn from(key: &str) -> Self {
+            Self(key.to_string())
+        }
+    }
+}
+
+use types::Key;
+
+/// Names its imports in bounds, where clauses, supertraits and trait objects.
+pub trait Keys: Store {
+    fn conv<K: Into<Key>>(&self, key: K) -> Key;
+    fn first<I>(&self, keys: I) -> Option<Key>
+    where
+        I: IntoIterator<Item = Key>;
+    fn make(&self, key: &str) -> Box<dyn Fn() -> Key>;
+}
+
+impl Keys for Mem {
+    fn conv<K: Into<Key>>(&self, key: K) -> Key {
+        key.into()
+    }
+
+    fn first<I>(&self, keys: I) -> Option<Key>
+    where
+        I: IntoIterator<Item = Key>,
+    {
+        keys.into_iter().find(|key| self.0.contains_key(&key.0))
+    }
+
+    fn make(&self, key: &str) -> Box<dyn Fn() -> Key> {
+        let key = Key(key.to_string());
+        Box::new(move || key.clone())
+    }
+}
+
+/// Returns `Result` and `Option`, for fallible targets.
+pub trait Lookup {
+    fn lookup(&self, key: &str) -> Result<u32, String>;
+    fn count(&self) -> Option<usize>;
+}
+
+impl Lookup for Mem {
+    fn lookup(&self, key: &str) -> Result<u32, String> {
+        self.get(key).ok_or_else(|| format!("No key {}.", key))
+    }
+
+    fn count(&self) -> Option<usize> {
+        Some(self.0.len())
+    }
+}
+
+/// Has associated items and a supertrait, for pointer impls.
+pub trait Named: Store {
+    type Name: AsRef<str>;
+    const PREFIX: &'static str;
+    fn name(&self) -> Self::Name;
+}
+
+impl Named for Mem {
+    type Name = String;
+    const PREFIX: &'static str = "mem";
+
+    fn name(&self) -> String {
+        format!("{}:{}", Self::PREFIX, self.0.len())
+    }
+}
+
+delegate_trait_fixture_macros::delegate_pointers!(Store);
+delegate_trait_fixture_macros::delegate_pointers!(Named);
+
+/// Registered with `delegate!` attributes on its methods.
+pub trait Versioned {
+    fn version(&self) -> u32;
+}
+
+impl Versioned for Mem {
+    fn version(&self) -> u32 {
+        1
+    }
+}
+
+/// Unsafe to implement, with an unsafe method.
+///
+/// # Safety
+///
+/// `raw` returns the number of entries.
+pub unsafe trait Raw {
+    /// # Safety
+    ///
+    /// Always safe, for the test of unsafe methods.
+    unsafe fn raw(&self) -> usize;
+}
+
+unsafe impl Raw for Mem {
+    unsafe fn raw(&self) -> usize {
+        self.0.len()
+    }
+}
+
+/// Associated functions, forwarded to `inner`.
+pub trait Build: Sized {
+    fn new() -> Self;
+    fn name() -> &'static str;
+    fn parse(key: &str) -> Option<Self>;
+    fn load(key: &str) -> Result<Self, String>;
+}
+
+impl Build for Mem {
+    fn new() -> Self {
+        let mut mem = Mem::default();
+        mem.set("new", 1);
+        mem
+    }
+
+    fn name() -> &'static str {
+        "mem"
+    }
+
+    fn parse(key: &str) -> Option<Self> {
+        (!key.is_empty()).then(|| {
+            let mut mem = Mem::default();
+            mem.set(key, 2);
+            mem
+        })
+    }
+
+    fn load(key: &str) -> Result<Self, String> {
+        Self::parse(key).ok_or_else(|| "Empty key.".to_string())
+    }
+}
+
+/// Takes `Self` arguments, projected to the same target as the receiver.
+pub trait Merge {
+    fn merge(&mut self, other: &Self);
+    fn drain(&mut self, other: &mut Self);
+    fn combine(self, other: Self) -> usize;
+}
+
+impl Merge for Mem {
+    fn merge(&mut self, other: &Self) {
+        self.0
+            .extend(other.0.iter().map(|(key, value)| (key.clone(), *value)));
+    }
+
+    fn drain(&mut self, other: &mut Self) {
+        self.0.extend(other.0.drain());
+    }
+
+    fn combine(mut self, other: Self) -> usize {
+        self.0.extend(other.0);
+        self.0.len()
+    }
+}
@@ -0,0 +1,66 @@
+use std::cell::RefCell;
+
+use delegate_trait_fixture::{Lookup, Mem, Store, Versioned};
+use delegate_trait_fixture_macros::delegate;
+
+thread_local! {
+    static CALLS: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+}
+
+fn trace<R>(trait_name: &str, method: &str, call: impl FnOnce() -> R) -> R {
+    CALLS.with(|calls| {
+        calls
+            .borrow_mut()
+            .push(format!("{}::{}", trait_name, method))
+    });
+    call()
+}
+
+fn calls() -> Vec<String> {
+    CALLS.with(|calls| calls.borrow_mut().drain(..).collect())
+}
+
+#[delegate(Store ref = &self.inner mut = &mut self.inner around = trace)]
+#[derive(Default)]
+struct Traced {
+    inner: Mem,
+}
+
+#[delegate(Lookup to self.inner.as_ref()? else "Not connected.".to_string() around = trace)]
+struct Lazy {
+    inner: Option<Mem>,
+}
+
+/// `#[into]` on the registered method is applied by the forwarding method.
+#[delegate(Versioned to &self.inner around = trace)]
+#[derive(Default)]
+struct TracedVersion {
+    inner: Mem,
+}
+
+#[test]
+fn around() {
+    let mut traced = Traced::default();
+    traced.set("a", 1);
+    assert_eq!(traced.get("a"), Some(1));
+    assert_eq!(calls(), ["Store::set", "Store::get"]);
+}
+
+#[test]
+fn around_fallible_target() {
+    let mut lazy = Lazy { inner: None };
+    assert_eq!(lazy.lookup("a"), Err("Not connected.".to_string()));
+    assert_eq!(lazy.count(), None);
+    // The target is missing before the hook is called.
+    assert!(calls().is_empty());
+
+    lazy.inner = Some(Mem::default());
+    assert_eq!(lazy.count(), Some(0));
+    assert_eq!(calls(), ["Lookup::count"]);
+}
+
+#[test]
+fn around_delegate_attrs() {
+    assert_eq!(TracedVersion::default().version(), 1);
+    assert_eq!(calls(), ["Versioned::version"]);
+}
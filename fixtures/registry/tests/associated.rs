@@ -0,0 +1,28 @@
+use delegate_trait_fixture::{Build, Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Build inner = Mem wrap = Wrapper::from)]
+struct Wrapper {
+    inner: Mem,
+}
+
+impl From<Mem> for Wrapper {
+    fn from(inner: Mem) -> Self {
+        Self { inner }
+    }
+}
+
+#[test]
+fn associated_functions() {
+    let wrapper = Wrapper::new();
+    assert_eq!(wrapper.inner.get("new"), Some(1));
+    assert_eq!(Wrapper::name(), "mem");
+}
+
+#[test]
+fn wrapped_returns() {
+    assert_eq!(Wrapper::parse("a").unwrap().inner.get("a"), Some(2));
+    assert!(Wrapper::parse("").is_none());
+    assert_eq!(Wrapper::load("b").unwrap().inner.get("b"), Some(2));
+    assert_eq!(Wrapper::load("").err(), Some("Empty key.".to_string()));
+}
@@ -0,0 +1,47 @@
+use std::cell::Cell;
+use std::sync::Mutex;
+
+use delegate_trait_fixture::{Lookup, Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+/// The guard is bound once per call, and borrowed as the target.
+#[delegate(Store to fn self.inner.lock().unwrap())]
+#[derive(Default)]
+struct Guarded {
+    inner: Mutex<Mem>,
+}
+
+/// The target is computed by a method, evaluated once per call.
+#[delegate(Lookup to fn self.backend())]
+struct Counted {
+    backends: Vec<Mem>,
+    calls: Cell<u32>,
+}
+
+impl Counted {
+    fn backend(&self) -> &Mem {
+        self.calls.set(self.calls.get() + 1);
+        &self.backends[0]
+    }
+}
+
+#[test]
+fn owned_guard() {
+    let mut guarded = Guarded::default();
+    guarded.set("a", 1);
+    assert_eq!(guarded.get("a"), Some(1));
+    assert_eq!(guarded.inner.lock().unwrap().get("a"), Some(1));
+}
+
+#[test]
+fn evaluated_once() {
+    let mut mem = Mem::default();
+    mem.set("a", 1);
+    let counted = Counted {
+        backends: vec![mem],
+        calls: Cell::new(0),
+    };
+    assert_eq!(counted.lookup("a"), Ok(1));
+    assert_eq!(counted.count(), Some(1));
+    assert_eq!(counted.calls.get(), 2);
+}
@@ -0,0 +1,22 @@
+#[callback::delegatable]
+pub trait Named {
+    fn name(&self) -> String;
+}
+
+struct Leaf;
+
+impl Named for Leaf {
+    fn name(&self) -> String {
+        "leaf".to_string()
+    }
+}
+
+#[callback::delegate(Named ref = &self.leaf)]
+struct Node {
+    leaf: Leaf,
+}
+
+#[test]
+fn renamed_macros_crate() {
+    assert_eq!(Node { leaf: Leaf }.name(), "leaf");
+}
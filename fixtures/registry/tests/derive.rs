@@ -0,0 +1,28 @@
+use delegate_trait_fixture::{Lookup, Mem, Store};
+use delegate_trait_fixture_macros::{delegate, Delegate};
+
+#[derive(Default, Delegate)]
+#[delegate_to(Store ref = &self.inner mut = &mut self.inner)]
+#[delegate_to(Lookup to &self.inner)]
+struct Derived {
+    inner: Mem,
+}
+
+/// The attribute macro still works next to the derive macro.
+#[delegate(Store ref = &self.inner mut = &mut self.inner)]
+#[derive(Default)]
+struct Attributed {
+    inner: Mem,
+}
+
+#[test]
+fn derive() {
+    let mut derived = Derived::default();
+    derived.set("a", 1);
+    assert_eq!(derived.get("a"), Some(1));
+    assert_eq!(derived.lookup("a"), Ok(1));
+
+    let mut attributed = Attributed::default();
+    attributed.set("a", 2);
+    assert_eq!(attributed.get("a"), Some(2));
+}
@@ -0,0 +1,48 @@
+use delegate_trait_fixture::{Lookup, Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Lookup to self.inner.as_ref()? else "Not connected.".to_string())]
+struct Lazy {
+    inner: Option<Mem>,
+}
+
+#[test]
+fn fallible_target() {
+    let mut lazy = Lazy { inner: None };
+    assert_eq!(lazy.lookup("a"), Err("Not connected.".to_string()));
+    assert_eq!(lazy.count(), None);
+
+    let mut mem = Mem::default();
+    mem.set("a", 1);
+    lazy.inner = Some(mem);
+    assert_eq!(lazy.lookup("a"), Ok(1));
+    assert_eq!(lazy.lookup("b"), Err("No key b.".to_string()));
+    assert_eq!(lazy.count(), Some(1));
+}
+
+/// The target is behind a `Result`, borrowed after `?`.
+#[delegate(Lookup to &*self.conn()? else "Not connected.".to_string())]
+struct Connected {
+    conn: Result<Mem, std::io::Error>,
+}
+
+impl Connected {
+    fn conn(&self) -> Result<&Mem, &std::io::Error> {
+        self.conn.as_ref()
+    }
+}
+
+#[test]
+fn result_target() {
+    let connected = Connected {
+        conn: Err(std::io::Error::other("refused")),
+    };
+    assert_eq!(connected.lookup("a"), Err("Not connected.".to_string()));
+    assert_eq!(connected.count(), None);
+
+    let connected = Connected {
+        conn: Ok(Mem::default()),
+    };
+    assert_eq!(connected.lookup("a"), Err("No key a.".to_string()));
+    assert_eq!(connected.count(), Some(0));
+}
@@ -0,0 +1,21 @@
+use delegate_trait_fixture::{Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Store ref = &self.inner mut = &mut self.inner
+    map_args(set(key)) = &$.to_uppercase()
+    map_args(value) = $ * 10
+    map_return(get) = $.map(|value| value + 1))]
+#[derive(Default)]
+struct Upper {
+    inner: Mem,
+}
+
+#[test]
+fn selectors() {
+    let mut upper = Upper::default();
+    upper.set("a", 1);
+    assert!(upper.inner.0.contains_key("A"));
+    // `key` is only mapped in `set`.
+    assert_eq!(upper.get("a"), None);
+    assert_eq!(upper.get("A"), Some(11));
+}
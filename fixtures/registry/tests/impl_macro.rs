@@ -0,0 +1,36 @@
+use delegate_trait_fixture::{Mem, Store};
+use delegate_trait_fixture_macros::delegate_impl;
+
+struct Wrapper<T>(T);
+
+delegate_impl!(Store for Wrapper<Mem> ref = &self.0 mut = &mut self.0);
+
+struct Generic<T>(T);
+
+delegate_impl!(impl<T: Store> Store for Generic<T> ref = &self.0 mut = &mut self.0);
+
+struct Bounded<T> {
+    inner: T,
+}
+
+delegate_impl!(impl<T> Store for Bounded<T> ref = &self.inner mut = &mut self.inner where T: Store);
+
+#[test]
+fn concrete() {
+    let mut wrapper = Wrapper(Mem::default());
+    wrapper.set("a", 1);
+    assert_eq!(wrapper.get("a"), Some(1));
+}
+
+#[test]
+fn generic() {
+    let mut generic = Generic(Mem::default());
+    generic.set("a", 1);
+    assert_eq!(generic.get("a"), Some(1));
+
+    let mut bounded = Bounded {
+        inner: Generic(Mem::default()),
+    };
+    bounded.set("b", 2);
+    assert_eq!(bounded.get("b"), Some(2));
+}
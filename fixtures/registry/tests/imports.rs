@@ -0,0 +1,23 @@
+use delegate_trait_fixture::types::Key as RenamedKey;
+use delegate_trait_fixture::{Keys, Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+// `Key` is not in scope here: the definition's imports are resolved in the registry.
+#[delegate(Store ref = &self.inner mut = &mut self.inner)]
+#[delegate(Keys ref = &self.inner)]
+#[derive(Default)]
+struct Wrapper {
+    inner: Mem,
+}
+
+#[test]
+fn imports_in_bounds() {
+    let mut wrapper = Wrapper::default();
+    wrapper.set("b", 2);
+    assert_eq!(wrapper.conv("a"), RenamedKey("a".to_string()));
+    assert_eq!(
+        wrapper.first(["a".into(), "b".into()]),
+        Some(RenamedKey("b".to_string()))
+    );
+    assert_eq!(wrapper.make("c")(), RenamedKey("c".to_string()));
+}
@@ -0,0 +1,17 @@
+use delegate_trait_fixture::{Mem, Named, Store};
+
+fn describe<N: Named>(named: &N) -> String {
+    format!("{} {}", N::PREFIX, named.name().as_ref())
+}
+
+#[test]
+fn pointer_impls() {
+    let mut mem = Box::new(Mem::default());
+    mem.set("a", 1);
+    assert_eq!(describe(&mem), "mem mem:1");
+
+    let mut inner = Mem::default();
+    let mut borrowed = &mut inner;
+    Store::set(&mut borrowed, "b", 2);
+    assert_eq!(describe(&borrowed), "mem mem:1");
+}
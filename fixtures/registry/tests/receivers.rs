@@ -0,0 +1,16 @@
+use delegate_trait_fixture::{Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Store ref = self.inner.as_deref().unwrap() mut = self.inner.as_deref_mut().unwrap())]
+struct Lazy {
+    inner: Option<Box<Mem>>,
+}
+
+#[test]
+fn targets_per_receiver() {
+    let mut lazy = Lazy {
+        inner: Some(Box::default()),
+    };
+    lazy.set("a", 1);
+    assert_eq!(lazy.get("a"), Some(1));
+}
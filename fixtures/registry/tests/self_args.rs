@@ -0,0 +1,36 @@
+use delegate_trait_fixture::{Mem, Merge, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Store ref = &self.inner mut = &mut self.inner)]
+#[delegate(Merge ref = &self.inner mut = &mut self.inner owned = self.inner)]
+#[derive(Default)]
+struct PerReceiver {
+    inner: Mem,
+}
+
+#[delegate(Store to self.0 via deref_mut())]
+#[delegate(Merge to self.0 via deref_mut())]
+#[derive(Default)]
+struct Boxed(Box<Mem>);
+
+#[test]
+fn projected_per_receiver() {
+    let (mut a, mut b) = (PerReceiver::default(), PerReceiver::default());
+    b.set("b", 2);
+    a.merge(&b);
+    assert_eq!(a.get("b"), Some(2));
+    a.drain(&mut b);
+    assert_eq!(b.get("b"), None);
+    assert_eq!(a.combine(b), 1);
+}
+
+#[test]
+fn projected_via() {
+    let (mut a, mut b) = (Boxed::default(), Boxed::default());
+    b.set("b", 2);
+    a.merge(&b);
+    assert_eq!(a.get("b"), Some(2));
+    a.drain(&mut b);
+    assert_eq!(b.get("b"), None);
+    assert_eq!(a.combine(b), 1);
+}
@@ -0,0 +1,16 @@
+use delegate_trait_fixture::{Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Store ref = &self.inner mut = &mut self.inner)]
+#[derive(Default)]
+struct Wrapper {
+    inner: Mem,
+}
+
+#[test]
+fn delegates_without_facade_crate() {
+    let mut wrapper = Wrapper::default();
+    wrapper.set("a", 1);
+    assert_eq!(wrapper.get("a"), Some(1));
+    assert_eq!(wrapper.inner.get("a"), Some(1));
+}
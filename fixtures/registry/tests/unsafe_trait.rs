@@ -0,0 +1,15 @@
+use delegate_trait_fixture::{Mem, Raw, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(unsafe Raw to &self.inner)]
+#[derive(Default)]
+struct Wrapper {
+    inner: Mem,
+}
+
+#[test]
+fn unsafe_trait() {
+    let mut wrapper = Wrapper::default();
+    wrapper.inner.set("a", 1);
+    assert_eq!(unsafe { wrapper.raw() }, 1);
+}
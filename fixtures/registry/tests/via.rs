@@ -0,0 +1,70 @@
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex, RwLock};
+
+use delegate_trait_fixture::{Mem, Store};
+use delegate_trait_fixture_macros::delegate;
+
+#[delegate(Store to self.inner via lock())]
+#[derive(Default)]
+struct Locked {
+    inner: Arc<Mutex<Mem>>,
+}
+
+#[delegate(Store to self.inner via write())]
+#[derive(Default)]
+struct ReadWrite {
+    inner: RwLock<Mem>,
+}
+
+#[delegate(Store to self.0 via borrow_mut())]
+#[derive(Default)]
+struct Cell(Rc<RefCell<Mem>>);
+
+#[delegate(Store to self.0 via deref_mut())]
+#[derive(Default)]
+struct Boxed(Box<Mem>);
+
+/// The target is bound once by reference, then reached through the adapter.
+#[delegate(Store to fn self.inner via lock())]
+#[derive(Default)]
+struct BoundLocked {
+    inner: Mutex<Mem>,
+}
+
+#[delegate(Store to fn self.0 via deref_mut())]
+#[derive(Default)]
+struct BoundBoxed(Box<Mem>);
+
+#[test]
+fn adapters() {
+    let mut locked = Locked::default();
+    locked.set("a", 1);
+    assert_eq!(locked.get("a"), Some(1));
+    assert_eq!(locked.inner.lock().unwrap().get("a"), Some(1));
+
+    let mut read_write = ReadWrite::default();
+    read_write.set("b", 2);
+    assert_eq!(read_write.get("b"), Some(2));
+
+    let mut cell = Cell::default();
+    cell.set("c", 3);
+    assert_eq!(cell.get("c"), Some(3));
+    assert_eq!(cell.0.borrow().get("c"), Some(3));
+
+    let mut boxed = Boxed::default();
+    boxed.set("d", 4);
+    assert_eq!(boxed.get("d"), Some(4));
+}
+
+#[test]
+fn bound_adapters() {
+    let mut locked = BoundLocked::default();
+    locked.set("a", 1);
+    assert_eq!(locked.get("a"), Some(1));
+    assert_eq!(locked.inner.lock().unwrap().get("a"), Some(1));
+
+    let mut boxed = BoundBoxed::default();
+    boxed.set("b", 2);
+    assert_eq!(boxed.get("b"), Some(2));
+}
@@ -0,0 +1,22 @@
+//! Attribute macros delegating traits of the current crate, without INPUT file nor build script.
+//! Expansions refer to `::delegate_trait`, which callers must depend on.
+
+use proc_macro::TokenStream;
+
+/// Makes a trait available to `#[delegate]`.
+#[proc_macro_attribute]
+pub fn delegatable(args: TokenStream, item: TokenStream) -> TokenStream {
+    delegate_trait::callback::delegatable(args.into(), item.into()).into()
+}
+
+/// Implements a `#[delegatable]` trait, e.g. `#[delegate(Store to &self.inner)]`.
+#[proc_macro_attribute]
+pub fn delegate(args: TokenStream, item: TokenStream) -> TokenStream {
+    delegate_trait::callback::delegate(args.into(), item.into()).into()
+}
+
+#[doc(hidden)]
+#[proc_macro]
+pub fn __delegate_callback(input: TokenStream) -> TokenStream {
+    delegate_trait::callback::delegate_callback(input.into()).into()
+}
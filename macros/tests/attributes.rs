@@ -0,0 +1,39 @@
+//! Attributes of trait methods: `cfg` and `allow` reach the impl, documentation and lint levels
+//! do not.
+
+use delegate_trait_macros::{delegatable, delegate};
+
+#[delegatable]
+pub trait Versioned {
+    /// Documented, and only kept on the trait.
+    #[must_use]
+    fn version(&self) -> u32;
+
+    #[cfg(any())]
+    fn removed(&self) -> MissingType;
+
+    #[cfg_attr(all(), allow(clippy::needless_lifetimes), doc = "Conditional.")]
+    fn name<'a>(&'a self) -> &'a str;
+}
+
+struct Release;
+
+impl Versioned for Release {
+    fn version(&self) -> u32 {
+        2
+    }
+
+    #[allow(clippy::needless_lifetimes)]
+    fn name<'a>(&'a self) -> &'a str {
+        "release"
+    }
+}
+
+#[delegate(Versioned ref = &self.0)]
+struct Latest(Release);
+
+#[test]
+fn method_attributes() {
+    assert_eq!(Latest(Release).version(), 2);
+    assert_eq!(Latest(Release).name(), "release");
+}
@@ -0,0 +1,63 @@
+use delegate_trait_macros::{delegatable, delegate};
+
+#[delegatable]
+pub trait Counter {
+    fn get(&self) -> u32;
+    fn add(&mut self, amount: u32) -> u32;
+}
+
+#[derive(Default)]
+struct Cell(u32);
+
+impl Counter for Cell {
+    fn get(&self) -> u32 {
+        self.0
+    }
+
+    fn add(&mut self, amount: u32) -> u32 {
+        self.0 += amount;
+        self.0
+    }
+}
+
+#[delegate(Counter ref = &self.inner mut = &mut self.inner)]
+#[derive(Default)]
+struct Named {
+    inner: Cell,
+}
+
+#[delegate(Counter ref = &self.0 mut = &mut self.0)]
+#[derive(Default)]
+struct Tuple(Named);
+
+#[test]
+fn delegates_to_field() {
+    let mut named = Named::default();
+    assert_eq!(named.add(2), 2);
+    assert_eq!(named.get(), 2);
+}
+
+#[test]
+fn delegates_through_delegated_impl() {
+    let mut tuple = Tuple::default();
+    tuple.add(3);
+    assert_eq!(tuple.add(4), 7);
+    assert_eq!(tuple.get(), 7);
+}
+
+mod nested {
+    use super::{Cell, Counter};
+    use delegate_trait_macros::delegate;
+
+    #[delegate(Counter ref = &self.cell mut = &mut self.cell)]
+    #[derive(Default)]
+    pub struct Imported {
+        cell: Cell,
+    }
+}
+
+#[test]
+fn trait_imported_with_use() {
+    let mut imported = nested::Imported::default();
+    assert_eq!(imported.add(5), 5);
+}
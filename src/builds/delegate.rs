@@ -1,31 +1,47 @@
 use std::path::Path;
 
-use proc_macro2::TokenStream;
+use proc_macro2::{Span, TokenStream};
 use quote::quote;
-use syn::{parse2, File};
 
-use crate::input::DelegateInput;
-use crate::parse_input;
+use super::delegate_impl::{impl_ident, pointers_ident};
+use super::unparse;
+use crate::input::{parse_input_then, DelegateInput};
+use crate::BuildError;
 
-pub fn generate_delegate_build_string<P: AsRef<Path>>(path: P) -> String {
-    match parse_input(path.as_ref()) {
-        Ok(input) => prettyplease::unparse(
-            &parse2::<File>(generate_crate_build(&input)).expect("prettyplease: unparse failed"),
-        ),
-        Err(err) => err.to_compile_error().to_string(),
-    }
+pub fn generate_delegate_build_string<P: AsRef<Path>>(path: P) -> Result<String, BuildError> {
+    let tokens = parse_input_then(path, |input| generate_crate_build(&input))?;
+    unparse(tokens)
 }
 
-fn generate_crate_build(input: &DelegateInput) -> TokenStream {
-    let delegate_impl_ident = &input.crate_impl_ident;
-    let macro_ident = &input.macro_ident;
+fn generate_crate_build(input: &DelegateInput) -> syn::Result<TokenStream> {
+    if input.is_single_crate() {
+        return Err(syn::Error::new(
+            Span::call_site(),
+            "No item `crate_ident` specified.",
+        ));
+    }
+    let delegate_impl_ident = input.crate_impl_ident.as_ref().ok_or(syn::Error::new(
+        Span::call_site(),
+        "No item `crate_impl_ident` specified.",
+    ))?;
+    let macro_idents = input
+        .macros
+        .iter()
+        .flat_map(|macro_input| {
+            [
+                macro_input.macro_ident.clone(),
+                pointers_ident(macro_input),
+                impl_ident(macro_input),
+            ]
+        })
+        .chain(input.derive_ident.clone());
 
-    quote! {
-        pub use #delegate_impl_ident:: #macro_ident;
+    Ok(quote! {
+        pub use #delegate_impl_ident::{#(#macro_idents),*};
 
         #[doc(hidden)]
         pub mod __private {
             pub use delegate_trait::delegate;
         }
-    }
+    })
 }
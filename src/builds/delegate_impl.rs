@@ -1,60 +1,227 @@
 use std::path::Path;
 
-use proc_macro2::TokenStream;
-use quote::quote;
-use syn::{parse2, File};
-
-use crate::input::DelegateInput;
-use crate::{generate_traits_match, parse_input};
-
-pub fn generate_delegate_impl_build_string<P: AsRef<Path>>(path: P) -> String {
-    match parse_input(path.as_ref()) {
-        Ok(input) => prettyplease::unparse(
-            &parse2::<File>(generate_crate_impl_build(&input))
-                .expect("prettyplease: unparse failed"),
-        ),
-        // Ok(input) => generate_crate_impl_build(&input).to_string(),
-        Err(err) => err.to_compile_error().to_string(),
+use proc_macro2::{Ident, TokenStream};
+use quote::{format_ident, quote};
+
+use super::unparse;
+use crate::input::{DelegateInput, MacroInput};
+use crate::{generate_traits_match, parse_input, BuildError};
+
+pub fn generate_delegate_impl_build_string<P: AsRef<Path>>(path: P) -> Result<String, BuildError> {
+    let input = parse_input(path.as_ref())?;
+    if input.self_crate.is_none() {
+        println!(
+            "cargo:warning={}: without `self_crate`, paths starting with the package using the macro are rewritten to `crate`. This fallback is deprecated, set `self_crate` to the crate defining the traits.",
+            path.as_ref().display()
+        );
     }
+    unparse(generate_crate_impl_build(&input))
 }
 
 fn generate_crate_impl_build(input: &DelegateInput) -> TokenStream {
-    let macro_ident = &input.macro_ident;
+    let macros = input
+        .macros
+        .iter()
+        .map(|macro_input| generate_macro(input, macro_input));
 
-    let traits_match = generate_traits_match(input);
+    let derive = input
+        .derive_ident
+        .as_ref()
+        .map(|derive_ident| generate_derive(input, derive_ident));
+
+    quote! {
+        #(#macros)*
+
+        #derive
+
+        fn derive(
+            args: &::delegate_trait::TraitConfig,
+            input: &::syn::DeriveInput,
+            try_expand: fn(&::delegate_trait::TraitConfig, &::syn::DeriveInput) -> ::syn::Result<::proc_macro2::TokenStream>,
+        ) -> ::proc_macro2::TokenStream {
+            match try_expand(args, input) {
+                Ok(expanded) => expanded,
+                Err(err) => {
+                    err.to_compile_error()
+                }
+            }
+        }
+    }
+}
+
+fn generate_macro(input: &DelegateInput, macro_input: &MacroInput) -> TokenStream {
+    let macro_ident = &macro_input.macro_ident;
+    let pointers_ident = pointers_ident(macro_input);
+    let try_expand = format_ident!("try_expand_{}", macro_ident);
+    let try_expand_pointers = format_ident!("try_expand_{}", pointers_ident);
+    let impl_ident = impl_ident(macro_input);
+    let try_expand_impl = format_ident!("try_expand_{}", impl_ident);
+    let dispatch = format_ident!("dispatch_{}", macro_ident);
+
+    let dispatch_fn = generate_dispatch(input, macro_input, &dispatch);
+
+    let doc_hidden = if input.is_single_crate() {
+        TokenStream::default()
+    } else {
+        quote! { #[doc(hidden)] }
+    };
 
     let hashtag = quote! { # };
     quote! {
-        #[doc(hidden)]
+        #doc_hidden
         #[proc_macro_attribute]
         pub fn #macro_ident (args: ::proc_macro::TokenStream, input: ::proc_macro::TokenStream) -> ::proc_macro::TokenStream {
             let args = ::syn::parse_macro_input!(args as ::delegate_trait::TraitConfig);
             let input = ::syn::parse_macro_input!(input as syn::DeriveInput);
-            let res = derive(&args, &input);
+            let res = derive(&args, &input, #try_expand);
             ::quote::quote! {#hashtag input #hashtag res}.into()
         }
 
-        fn derive(args: &::delegate_trait::TraitConfig, input: &::syn::DeriveInput) -> ::proc_macro2::TokenStream {
-            match try_expand(args, input) {
+        #doc_hidden
+        #[proc_macro]
+        pub fn #pointers_ident (input: ::proc_macro::TokenStream) -> ::proc_macro::TokenStream {
+            let config = ::syn::parse_macro_input!(input as ::delegate_trait::TraitConfig);
+            match #try_expand_pointers(&config) {
                 Ok(expanded) => expanded,
-                Err(err) => {
-                    err.to_compile_error()
+                Err(err) => err.to_compile_error(),
+            }
+            .into()
+        }
+
+        #doc_hidden
+        #[proc_macro]
+        pub fn #impl_ident (input: ::proc_macro::TokenStream) -> ::proc_macro::TokenStream {
+            let input = ::syn::parse_macro_input!(input as ::delegate_trait::ImplInput);
+            match #try_expand_impl(&input) {
+                Ok(expanded) => expanded,
+                Err(err) => err.to_compile_error(),
+            }
+            .into()
+        }
+
+        fn #try_expand(config: &::delegate_trait::TraitConfig, input: &::syn::DeriveInput) -> ::syn::Result<::proc_macro2::TokenStream> {
+            let context = ::delegate_trait::Context::new(input);
+            #dispatch(config, |config, root, trait_input| {
+                ::delegate_trait::generate_trait_impl(&context, config, root, trait_input)
+            })
+        }
+
+        fn #try_expand_impl(input: &::delegate_trait::ImplInput) -> ::syn::Result<::proc_macro2::TokenStream> {
+            let context = input.context();
+            #dispatch(&input.config, |config, root, trait_input| {
+                ::delegate_trait::generate_trait_impl(&context, config, root, trait_input)
+            })
+        }
+
+        fn #try_expand_pointers(config: &::delegate_trait::TraitConfig) -> ::syn::Result<::proc_macro2::TokenStream> {
+            #dispatch(config, ::delegate_trait::generate_pointer_impls)
+        }
+
+        #dispatch_fn
+    }
+}
+
+/// Derive macro taking one `#[<derive_attr>(...)]` attribute per trait, among the traits of
+/// every attribute macro.
+fn generate_derive(input: &DelegateInput, derive_ident: &Ident) -> TokenStream {
+    let fn_ident = format_ident!("derive_{}", derive_ident.to_string().to_lowercase());
+    let try_expand = format_ident!("try_expand_{}", fn_ident);
+    let dispatch = format_ident!("dispatch_{}", fn_ident);
+    let derive_attr = input.derive_attr();
+    let derive_attr_name = derive_attr.to_string();
+    let missing = format!(
+        "Expected at least one `#[{}(Trait ...)]` attribute naming the trait to implement.",
+        derive_attr
+    );
+
+    let all_traits = MacroInput {
+        macro_ident: derive_ident.clone(),
+        traits: input
+            .macros
+            .iter()
+            .flat_map(|macro_input| macro_input.traits.iter().cloned())
+            .collect(),
+    };
+    let dispatch_fn = generate_dispatch(input, &all_traits, &dispatch);
+
+    let doc_hidden = if input.is_single_crate() {
+        TokenStream::default()
+    } else {
+        quote! { #[doc(hidden)] }
+    };
+
+    quote! {
+        #doc_hidden
+        #[proc_macro_derive(#derive_ident, attributes(#derive_attr))]
+        pub fn #fn_ident (input: ::proc_macro::TokenStream) -> ::proc_macro::TokenStream {
+            let input = ::syn::parse_macro_input!(input as syn::DeriveInput);
+            let attrs = input
+                .attrs
+                .iter()
+                .filter(|attr| attr.path().is_ident(#derive_attr_name))
+                .collect::<Vec<_>>();
+            if attrs.is_empty() {
+                return ::syn::Error::new_spanned(&input.ident, #missing)
+                    .to_compile_error()
+                    .into();
+            }
+            let mut res = ::proc_macro2::TokenStream::new();
+            for attr in attrs {
+                match attr.parse_args::<::delegate_trait::TraitConfig>() {
+                    Ok(args) => res.extend(derive(&args, &input, #try_expand)),
+                    Err(err) => res.extend(err.to_compile_error()),
                 }
             }
+            res.into()
         }
 
-        fn try_expand(config: &::delegate_trait::TraitConfig, input: &::syn::DeriveInput) -> ::syn::Result<::proc_macro2::TokenStream> {
+        fn #try_expand(config: &::delegate_trait::TraitConfig, input: &::syn::DeriveInput) -> ::syn::Result<::proc_macro2::TokenStream> {
             let context = ::delegate_trait::Context::new(input);
+            #dispatch(config, |config, root, trait_input| {
+                ::delegate_trait::generate_trait_impl(&context, config, root, trait_input)
+            })
+        }
 
+        #dispatch_fn
+    }
+}
+
+/// Function matching the trait named in the config with the traits of `macro_input`, and
+/// running `generate` on the registered definition.
+fn generate_dispatch(
+    input: &DelegateInput,
+    macro_input: &MacroInput,
+    dispatch: &Ident,
+) -> TokenStream {
+    let traits_match = generate_traits_match(input, macro_input);
+    quote! {
+        // The derive macro matches the traits of every macro, which may register the same one.
+        #[allow(unreachable_patterns)]
+        fn #dispatch(
+            config: &::delegate_trait::TraitConfig,
+            generate: impl Fn(&::delegate_trait::TraitConfig, ::syn::Path, ::delegate_trait::ItemTraitPath) -> ::syn::Result<::proc_macro2::TokenStream>,
+        ) -> ::syn::Result<::proc_macro2::TokenStream> {
             let trait_ident = &config.path.segments.last().expect("try_expand: Ident expected").ident;
             let trait_ident_string = trait_ident.to_string();
 
             let trait_implem: ::proc_macro2::TokenStream = match trait_ident_string.as_str() {
                 #traits_match
-                _ => return ::syn::Result::Err(::syn::Error::new_spanned(trait_ident, &format!("Unknown trait {}.", trait_ident_string))),
+                _ => return ::syn::Result::Err(::syn::Error::new_spanned(trait_ident, format!("Unknown trait {}.", trait_ident_string))),
             };
 
             Ok(trait_implem)
         }
     }
 }
+
+/// Function-like macro implementing the traits of `macro_input` for pointers, e.g.
+/// `delegate_pointers!(Store)` for the `delegate` macro.
+pub(crate) fn pointers_ident(macro_input: &MacroInput) -> Ident {
+    format_ident!("{}_pointers", macro_input.macro_ident)
+}
+
+/// Function-like macro implementing the traits of `macro_input` for an explicit type, e.g.
+/// `delegate_impl!(Store for Wrapper<Mem> to self.0)` for the `delegate` macro.
+pub(crate) fn impl_ident(macro_input: &MacroInput) -> Ident {
+    format_ident!("{}_impl", macro_input.macro_ident)
+}
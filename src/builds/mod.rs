@@ -1,39 +1,169 @@
+use std::ffi::OsStr;
+use std::path::Path;
+
+use proc_macro2::TokenStream;
+use syn::{parse2, File};
+
+use crate::BuildError;
+
 mod delegate;
 pub use delegate::generate_delegate_build_string;
 
 mod delegate_impl;
 pub use delegate_impl::generate_delegate_impl_build_string;
 
+/// Runs `generate` on the INPUT file and writes the result to `$OUT_DIR/lib.rs`.
+///
+/// Errors are reported as `cargo:warning=` lines pointing into INPUT, and the build script then
+/// exits with a failure code instead of panicking.
+pub fn write_build<F>(input: &str, generate: F)
+where
+    F: FnOnce(&Path) -> Result<String, BuildError>,
+{
+    println!("cargo:rerun-if-changed=./build.rs");
+    println!("cargo:rerun-if-changed={}", input);
+    // Lets dependents `extends` this registry when the crate declares `links`.
+    if let Ok(canonical) = Path::new(input).canonicalize() {
+        println!("cargo:input={}", canonical.display());
+    }
+
+    let out_dir = std::env::var_os("OUT_DIR");
+    if let Err(err) = try_write_build(Path::new(input), out_dir.as_deref(), generate) {
+        err.emit();
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+fn try_write_build<F>(input: &Path, out_dir: Option<&OsStr>, generate: F) -> Result<(), BuildError>
+where
+    F: FnOnce(&Path) -> Result<String, BuildError>,
+{
+    let out_dir = out_dir.ok_or(BuildError::Env { var: "OUT_DIR" })?;
+    let out_path = Path::new(out_dir).join("lib.rs");
+
+    let expanded = generate(input)?;
+    std::fs::write(&out_path, expanded).map_err(|source| BuildError::Write {
+        path: out_path,
+        source,
+    })
+}
+
+/// Formats the generated crate.
+fn unparse(tokens: TokenStream) -> Result<String, BuildError> {
+    let file = parse2::<File>(tokens).map_err(|error| BuildError::Generate { error })?;
+    Ok(prettyplease::unparse(&file))
+}
+
 #[macro_export]
 macro_rules! build_delegate {
     () => {
+        $crate::build_delegate!("./INPUT");
+    };
+    ($path:literal) => {
         fn main() {
-            println!("cargo:rerun-if-changed=./build.rs");
-            println!("cargo:rerun-if-changed=./INPUT");
-
-            let out_dir = ::std::env::var_os("OUT_DIR").expect("Could not read en var `OUT_DIR`");
-            let out_path = ::std::path::Path::new(&out_dir);
-
-            let input = $crate::builds::generate_delegate_build_string("./INPUT");
-            ::std::fs::write(out_path.join("lib.rs"), input)
-                .expect("Could not write expanded macro.");
+            $crate::builds::write_build($path, |path| {
+                $crate::builds::generate_delegate_build_string(path)
+            });
         }
     };
 }
 
+/// Build script of the proc-macro crate.
+///
+/// Reads `../INPUT` by default. In single-crate mode (no `crate_ident` in INPUT), the proc-macro
+/// crate is the only one and usually owns its INPUT: use `build_delegate_impl!("./INPUT")`.
 #[macro_export]
 macro_rules! build_delegate_impl {
     () => {
+        $crate::build_delegate_impl!("../INPUT");
+    };
+    ($path:literal) => {
         fn main() {
-            println!("cargo:rerun-if-changed=./build.rs");
-            println!("cargo:rerun-if-changed=../INPUT");
-
-            let out_dir = ::std::env::var_os("OUT_DIR").expect("Could not read en var `OUT_DIR`");
-            let out_path = ::std::path::Path::new(&out_dir);
-
-            let input = $crate::builds::generate_delegate_impl_build_string("../INPUT");
-            ::std::fs::write(out_path.join("lib.rs"), input)
-                .expect("Could not write expanded macro.");
+            $crate::builds::write_build($path, |path| {
+                $crate::builds::generate_delegate_impl_build_string(path)
+            });
         }
     };
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use quote::quote;
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    #[test]
+    fn writes_generated_crate() {
+        let dir = TestDir::with_files(&[]);
+        try_write_build(Path::new("INPUT"), Some(dir.as_os_str()), |input| {
+            assert_eq!(input, Path::new("INPUT"));
+            Ok("pub fn generated() {}\n".to_string())
+        })
+        .unwrap();
+        assert_eq!(
+            std::fs::read_to_string(dir.join("lib.rs")).unwrap(),
+            "pub fn generated() {}\n"
+        );
+    }
+
+    #[test]
+    fn missing_out_dir() {
+        let err = try_write_build(Path::new("INPUT"), None, |_| Ok(String::new())).unwrap_err();
+        assert!(matches!(err, BuildError::Env { var: "OUT_DIR" }));
+        assert_eq!(err.to_string(), "Could not read env var `OUT_DIR`.");
+    }
+
+    #[test]
+    fn generate_error_is_returned() {
+        let dir = TestDir::with_files(&[]);
+        let err = try_write_build(Path::new("INPUT"), Some(dir.as_os_str()), |_| {
+            Err(BuildError::Config {
+                path: PathBuf::from("INPUT.toml"),
+                line: 3,
+                key: Some("traits".to_string()),
+                message: "Missing key.".to_string(),
+            })
+        })
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "error: `traits`: Missing key.\n --> INPUT.toml:3"
+        );
+        assert!(!dir.join("lib.rs").exists());
+    }
+
+    #[test]
+    fn write_error() {
+        let parent = TestDir::with_files(&[]);
+        let dir = parent.join("missing");
+        let err = try_write_build(Path::new("INPUT"), Some(dir.as_os_str()), |_| {
+            Ok(String::new())
+        })
+        .unwrap_err();
+        assert!(matches!(&err, BuildError::Write { path, .. } if *path == dir.join("lib.rs")));
+        assert!(err.to_string().starts_with("Could not write "));
+    }
+
+    #[test]
+    fn unparse_formats() {
+        assert_eq!(
+            unparse(quote! { pub use a::{b, c}; }).unwrap(),
+            "pub use a::{b, c};\n"
+        );
+    }
+
+    #[test]
+    fn unparse_invalid_code() {
+        let err = unparse(quote! { pub fn }).unwrap_err();
+        assert!(matches!(err, BuildError::Generate { .. }));
+        assert_eq!(
+            err.to_string(),
+            "Could not format the generated code: unexpected end of input, expected identifier. \
+             Please report this bug."
+        );
+    }
+}
@@ -0,0 +1,129 @@
+//! "macro_rules callback" mode, implemented by the `delegate-trait-macros` crate.
+//!
+//! `#[delegatable]` stores the tokens of a trait in a hidden `macro_rules!`, imported under the
+//! trait name in the macro namespace so that `use`-ing the trait also brings it in scope.
+//! `#[delegate(Trait to ...)]` invokes that macro, which forwards the trait definition to
+//! `__delegate_callback!` along with the macro arguments and the annotated item. Traits defined
+//! in the same crate can then be delegated without INPUT file nor build script.
+
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote};
+use syn::parse::{Parse, ParseStream};
+use syn::{braced, parse2, DeriveInput, ItemTrait};
+
+use crate::self_crate::dependency_path;
+use crate::{generate_trait_impl, Context, ItemTraitPath, TraitConfig};
+
+/// Crates the expansions refer to, reached under the names the caller depends on them with.
+const MACROS_CRATE: &str = "delegate_trait_macros";
+const ROOT_CRATE: &str = "delegate_trait";
+
+pub fn delegatable(args: TokenStream, item: TokenStream) -> TokenStream {
+    try_delegatable(args, item).unwrap_or_else(|err| err.to_compile_error())
+}
+
+fn try_delegatable(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    if !args.is_empty() {
+        return Err(syn::Error::new_spanned(args, "Expected no arguments."));
+    }
+    let item_trait = parse2::<ItemTrait>(item)?;
+
+    let trait_ident = &item_trait.ident;
+    let macro_ident = format_ident!("__delegate_trait_{}", trait_ident);
+
+    let mut definition = item_trait.clone();
+    definition.attrs.clear();
+
+    Ok(quote! {
+        #item_trait
+
+        #[doc(hidden)]
+        #[allow(unused_macros)]
+        macro_rules! #macro_ident {
+            (:: $($callback:ident)::+ { $($args:tt)* } { $($item:tt)* }) => {
+                :: $($callback)::+ ! { { $($args)* } { $($item)* } { #definition } }
+            };
+        }
+
+        #[doc(hidden)]
+        #[allow(unused_imports)]
+        pub(crate) use #macro_ident as #trait_ident;
+    })
+}
+
+pub fn delegate(args: TokenStream, item: TokenStream) -> TokenStream {
+    try_delegate(args, item).unwrap_or_else(|err| err.to_compile_error())
+}
+
+fn try_delegate(args: TokenStream, item: TokenStream) -> syn::Result<TokenStream> {
+    let config = parse2::<TraitConfig>(args.clone())?;
+    let macros_crate = dependency_path(MACROS_CRATE)?;
+    let trait_path = &config.path;
+
+    Ok(quote! {
+        #item
+
+        #trait_path! { #macros_crate::__delegate_callback { #args } { #item } }
+    })
+}
+
+pub fn delegate_callback(input: TokenStream) -> TokenStream {
+    try_delegate_callback(input).unwrap_or_else(|err| err.to_compile_error())
+}
+
+fn try_delegate_callback(input: TokenStream) -> syn::Result<TokenStream> {
+    let CallbackInput {
+        config,
+        item,
+        trait_input,
+    } = parse2::<CallbackInput>(input)?;
+
+    // The definition went through `macro_rules!`, whose hygiene would keep its `self` apart from
+    // the one in `to`: resolve it at the caller instead.
+    let caller = config.path.segments[0].ident.span();
+    let trait_input = parse2::<ItemTraitPath>(resolved_at(trait_input, caller))?;
+
+    let context = Context::new(&item);
+    let root = dependency_path(ROOT_CRATE)?;
+
+    generate_trait_impl(&context, &config, root, trait_input)
+}
+
+fn resolved_at(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|mut token| {
+            if let TokenTree::Group(group) = &token {
+                let mut resolved = Group::new(group.delimiter(), resolved_at(group.stream(), span));
+                resolved.set_span(group.span().resolved_at(span));
+                token = TokenTree::Group(resolved);
+            } else {
+                token.set_span(token.span().resolved_at(span));
+            }
+            token
+        })
+        .collect()
+}
+
+struct CallbackInput {
+    config: TraitConfig,
+    item: DeriveInput,
+    trait_input: TokenStream,
+}
+
+impl Parse for CallbackInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let config;
+        braced!(config in input);
+        let item;
+        braced!(item in input);
+        let trait_input;
+        braced!(trait_input in input);
+
+        Ok(Self {
+            config: config.parse()?,
+            item: item.parse()?,
+            trait_input: trait_input.parse()?,
+        })
+    }
+}
@@ -1,36 +1,211 @@
-use proc_macro2::{Ident, TokenStream};
+use std::borrow::Cow;
+
+use proc_macro2::{Ident, Span, TokenStream};
 use quote::{quote, ToTokens};
 use syn::parse::Parse;
-use syn::{parse2, GenericParam, Generics, PathArguments, Token, WhereClause};
+use syn::{
+    parse2, ExprParen, ExprReference, ExprTry, ExprUnary, Generics, PathArguments, ReturnType,
+    Signature, Token, Type, UnOp, WhereClause,
+};
 
 use crate::generics::{merge_generics, merge_where_clauses};
+use crate::target::{ReceiverKind, Via};
+use crate::template::{self, Hook, Selector};
 use crate::Context;
 
+#[derive(Clone)]
 pub struct TraitConfig {
+    /// `unsafe` acknowledging the contract of an unsafe trait, required to implement one.
+    pub unsafety: Option<Token![unsafe]>,
     pub path: syn::Path,
     pub generics: syn::Generics,
-    pub to: syn::Expr,
+    /// Path of the crate defining the trait, overriding the one resolved from `self_crate`.
+    pub self_crate: Option<syn::Path>,
+    pub to: Option<syn::Expr>,
+    /// `fn` in `to fn <expr>`: `to` is evaluated exactly once per method, bound to a local at the
+    /// start of the forwarding method instead of being inlined in the call. Every method is then
+    /// forwarded without delegate!, the local is borrowed as the target, and targets specific to a
+    /// receiver are still inlined.
+    pub bind: Option<Token![fn]>,
+    /// Adapter through which `to` is reached, e.g. `via lock()`.
+    pub via: Option<Via>,
+    /// Targets of `&self` (`ref = ...`), `&mut self` (`mut = ...`) and `self` (`owned = ...`)
+    /// methods, taking precedence over `to`.
+    pub to_ref: Option<syn::Expr>,
+    pub to_mut: Option<syn::Expr>,
+    pub to_owned: Option<syn::Expr>,
+    /// Error returned by `Result` methods when a fallible target (`to <expr>?`) is missing, from
+    /// `else <expr>`.
+    pub missing: Option<syn::Expr>,
+    /// Type the associated functions of the trait are forwarded to.
+    pub inner: Option<syn::Type>,
+    /// Constructor building `Self` back from the inner value, for functions returning `Self`,
+    /// `Option<Self>` or `Result<Self, E>`.
+    pub wrap: Option<syn::Expr>,
+    /// Function called as `around("Trait", "method", || call)` by every forwarded method, e.g. to
+    /// trace or time it.
+    pub around: Option<syn::Path>,
+    /// Hooks converting the arguments passed to the inner implementation.
+    pub map_args: Vec<Hook>,
+    /// Hooks converting the values it returns.
+    pub map_return: Vec<Hook>,
     pub wh: Option<WhereClause>,
     pub wi: Option<TokenStream>,
 }
 
-#[derive(Debug, Clone, Hash, PartialEq, Eq)]
-enum GenericIdent<'a> {
-    Lifetime(&'a Ident),
-    Other(&'a Ident),
+/// Per-trait values used when the macro arguments leave them out.
+#[derive(Clone, Default)]
+pub struct TraitDefaults {
+    pub to: Option<syn::Expr>,
+    pub bind: Option<Token![fn]>,
+    pub wh: Option<WhereClause>,
 }
 
-impl<'a> From<&'a GenericParam> for GenericIdent<'a> {
-    fn from(generic_param: &'a GenericParam) -> Self {
-        match generic_param {
-            GenericParam::Lifetime(l) => Self::Lifetime(&l.lifetime.ident),
-            GenericParam::Type(t) => Self::Other(&t.ident),
-            GenericParam::Const(c) => Self::Other(&c.ident),
-        }
+impl TraitDefaults {
+    pub fn is_empty(&self) -> bool {
+        self.to.is_none() && self.wh.is_none()
     }
 }
 
 impl TraitConfig {
+    pub fn with_defaults(&self, defaults: &TraitDefaults) -> Self {
+        let mut config = self.clone();
+        if config.to.is_none() {
+            config.to = defaults.to.clone();
+            config.bind = defaults.bind;
+        }
+        if config.wh.is_none() {
+            config.wh = defaults.wh.clone();
+        }
+        config
+    }
+
+    pub fn to(&self) -> syn::Result<&syn::Expr> {
+        self.to.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(&self.path, "No target specified, expected `to <expr>`.")
+        })
+    }
+
+    /// Target specific to methods taking `kind`, e.g. `mut = ...` for `&mut self`.
+    pub fn receiver_target(&self, kind: ReceiverKind) -> Option<&syn::Expr> {
+        match kind {
+            ReceiverKind::Ref => self.to_ref.as_ref(),
+            ReceiverKind::Mut => self.to_mut.as_ref(),
+            ReceiverKind::Owned => self.to_owned.as_ref(),
+        }
+    }
+
+    /// Target given to the method with signature `sig`, which must have a receiver.
+    pub fn method_target(&self, sig: &Signature) -> syn::Result<TokenStream> {
+        let kind = ReceiverKind::of(sig.receiver().expect("method_target: expected receiver"));
+        if let Some(target) = self.receiver_target(kind) {
+            return Ok(self.fallible(target, sig)?.to_token_stream());
+        }
+        let to = self.to.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(
+                &self.path,
+                format!(
+                    "No target for methods taking {}, expected `{} = <expr>` or `to <expr>`.",
+                    kind.describe(),
+                    kind.key()
+                ),
+            )
+        })?;
+        let to = match self.bind {
+            // Bound by reference for the adapter, except for methods taking `self`.
+            Some(_) if self.via.is_some() && kind != ReceiverKind::Owned => {
+                let bound = bound_ident();
+                Cow::Owned(syn::parse_quote! { (*#bound) })
+            }
+            Some(_) => {
+                let bound = bound_ident();
+                Cow::Owned(match kind {
+                    ReceiverKind::Ref => syn::parse_quote! { &*#bound },
+                    ReceiverKind::Mut => syn::parse_quote! { &mut *#bound },
+                    ReceiverKind::Owned => syn::parse_quote! { #bound },
+                })
+            }
+            None => self.fallible(to, sig)?,
+        };
+        match &self.via {
+            Some(via) => via.access(&to, kind),
+            None => Ok(to.to_token_stream()),
+        }
+    }
+
+    /// Value of `to fn <expr>` bound at the start of the method with signature `sig`, when the
+    /// method has a receiver and no target specific to it.
+    pub fn bound_target(&self, sig: &Signature) -> syn::Result<Option<TokenStream>> {
+        let Some(receiver) = sig.receiver() else {
+            return Ok(None);
+        };
+        let kind = ReceiverKind::of(receiver);
+        if self.bind.is_none() || self.receiver_target(kind).is_some() {
+            return Ok(None);
+        }
+        let value = self.fallible(self.to()?, sig)?;
+        let bound = bound_ident();
+        Ok(Some(match (&self.via, kind) {
+            // The adapter reaches the target in place, e.g. `self.inner.lock()`.
+            (Some(_), ReceiverKind::Ref) => quote! { let #bound = &(#value); },
+            (Some(_), ReceiverKind::Mut) => quote! { let #bound = &mut (#value); },
+            (Some(_), ReceiverKind::Owned) => quote! { let #bound = #value; },
+            // Borrowed for the call, so that the value can be a reference, a guard or an owned
+            // value dereferencing to the target.
+            (None, _) => quote! {
+                #[allow(unused_mut)]
+                let mut #bound = #value;
+            },
+        }))
+    }
+
+    /// Checks that a fallible target, e.g. `self.conn.as_ref()?` or `&self.conn()?`, is only
+    /// used by methods returning `Result` or `Option`, and converts its operand, an `Option` or a
+    /// `Result`, to the return type: `None` for `Option` methods, and the error given by `else`,
+    /// which they require, for `Result` methods.
+    fn fallible<'a>(
+        &self,
+        target: &'a syn::Expr,
+        sig: &Signature,
+    ) -> syn::Result<Cow<'a, syn::Expr>> {
+        let Some(operand) = try_operand(target) else {
+            return Ok(Cow::Borrowed(target));
+        };
+        // Both `Option` and `Result` iterate over their value.
+        let value = quote! { ::core::iter::IntoIterator::into_iter(#operand).next() };
+
+        match returned(&sig.output) {
+            Some(Returned::Result) => match &self.missing {
+                Some(missing) => Ok(Cow::Owned(replace_try(
+                    target,
+                    syn::parse_quote! { #value.ok_or_else(|| #missing)? },
+                ))),
+                None => Err(syn::Error::new_spanned(
+                    target,
+                    format!(
+                        "Method {} returns `Result`, expected `else <error>` after the targets to return when the fallible target is missing.",
+                        sig.ident
+                    ),
+                )),
+            },
+            Some(Returned::Option) => Ok(Cow::Owned(replace_try(
+                target,
+                syn::parse_quote! { #value? },
+            ))),
+            None => Err(syn::Error::new_spanned(
+                target,
+                format!(
+                    "Method {} returns `{}`, only methods returning `Result` or `Option` can use a fallible target.",
+                    sig.ident,
+                    match &sig.output {
+                        ReturnType::Type(_, ty) => ty.to_token_stream().to_string(),
+                        ReturnType::Default => "()".to_string(),
+                    }
+                ),
+            )),
+        }
+    }
+
     pub fn wrap_methods(
         &self,
         context: &Context<'_>,
@@ -38,31 +213,109 @@ impl TraitConfig {
         trait_generics: &Generics,
         methods: &TokenStream,
     ) -> TokenStream {
-        let (_, ty_generics, _) = context.generics.split_for_impl();
-
-        let mut impl_generics = context.generics.clone();
+        let mut impl_generics = context.generics.clone().into_owned();
         merge_generics(&mut impl_generics, &self.generics);
 
         let mut where_clause = context.generics.where_clause.clone();
         merge_where_clauses(&mut where_clause, &self.wh, false);
 
-        let item_ident = &context.ident;
+        let self_ty = &context.self_ty;
+        let unsafety = &self.unsafety;
 
         quote! {
-            impl #impl_generics #trait_ident #trait_generics for #item_ident #ty_generics #where_clause {
+            #unsafety impl #impl_generics #trait_ident #trait_generics for #self_ty #where_clause {
                 #methods
             }
         }
     }
 }
 
+/// Operand of the `?` of a fallible target, under borrows, dereferences and parentheses, e.g.
+/// `self.conn()` in `&*self.conn()?`.
+fn try_operand(target: &syn::Expr) -> Option<&syn::Expr> {
+    match target {
+        syn::Expr::Try(ExprTry { expr, .. }) => Some(expr),
+        syn::Expr::Reference(ExprReference { expr, .. })
+        | syn::Expr::Paren(ExprParen { expr, .. })
+        | syn::Expr::Unary(ExprUnary {
+            op: UnOp::Deref(_),
+            expr,
+            ..
+        }) => try_operand(expr),
+        _ => None,
+    }
+}
+
+/// `target` with its `?` expression, see [`try_operand`], replaced by `value`.
+fn replace_try(target: &syn::Expr, value: syn::Expr) -> syn::Expr {
+    let mut target = target.clone();
+    let mut expr = &mut target;
+    while let syn::Expr::Reference(ExprReference { expr: inner, .. })
+    | syn::Expr::Paren(ExprParen { expr: inner, .. })
+    | syn::Expr::Unary(ExprUnary {
+        op: UnOp::Deref(_),
+        expr: inner,
+        ..
+    }) = expr
+    {
+        expr = inner;
+    }
+    *expr = value;
+    target
+}
+
+#[derive(Debug, PartialEq)]
+enum Returned {
+    Result,
+    Option,
+}
+
+/// Whether `output` is a `Result` or an `Option`: `Result`, `Option`, their full paths in `std`
+/// or `core`, or aliases of `Result` in another module such as `io::Result`.
+fn returned(output: &ReturnType) -> Option<Returned> {
+    let ReturnType::Type(_, ty) = output else {
+        return None;
+    };
+    let Type::Path(path) = &**ty else {
+        return None;
+    };
+    if path.qself.is_some() {
+        return None;
+    }
+    let segments = path
+        .path
+        .segments
+        .iter()
+        .map(|segment| segment.ident.to_string())
+        .collect::<Vec<_>>();
+    let segments = segments.iter().map(String::as_str).collect::<Vec<_>>();
+    match segments.as_slice() {
+        ["Result"] | ["std" | "core", "result", "Result"] => Some(Returned::Result),
+        ["Option"] | ["std" | "core", "option", "Option"] => Some(Returned::Option),
+        [_, .., "Result"] => Some(Returned::Result),
+        _ => None,
+    }
+}
+
+/// Local holding the target bound by `to fn <expr>`.
+fn bound_ident() -> Ident {
+    Ident::new("__delegate_target", Span::call_site())
+}
+
 mod keyword {
+    syn::custom_keyword!(around);
+    syn::custom_keyword!(inner);
+    syn::custom_keyword!(owned);
+    syn::custom_keyword!(self_crate);
     syn::custom_keyword!(to);
+    syn::custom_keyword!(via);
     syn::custom_keyword!(with);
+    syn::custom_keyword!(wrap);
 }
 
 impl Parse for TraitConfig {
     fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let unsafety = input.parse::<Option<Token![unsafe]>>()?;
         let mut path = input.parse::<syn::Path>()?;
 
         let arguments = core::mem::replace(
@@ -75,9 +328,105 @@ impl Parse for TraitConfig {
         );
         let generics: Generics = parse2(arguments.to_token_stream())?;
 
-        input.parse::<keyword::to>()?;
+        let self_crate = if input.peek(keyword::self_crate) {
+            input.parse::<keyword::self_crate>()?;
+            input.parse::<Token![=]>()?;
+            Some(input.parse::<syn::Path>()?)
+        } else {
+            None
+        };
+
+        let (to, bind) = if input.peek(keyword::to) {
+            input.parse::<keyword::to>()?;
+            let bind = input.parse::<Option<Token![fn]>>()?;
+            (Some(syn::Expr::parse_without_eager_brace(input)?), bind)
+        } else {
+            (None, None)
+        };
+
+        let via = if input.peek(keyword::via) {
+            input.parse::<keyword::via>()?;
+            Some(input.parse::<Via>()?)
+        } else {
+            None
+        };
+
+        let to_ref = parse_receiver_target::<Token![ref]>(input)?;
+        let to_mut = parse_receiver_target::<Token![mut]>(input)?;
+        let to_owned = parse_receiver_target::<keyword::owned>(input)?;
+
+        let missing = if input.peek(Token![else]) {
+            let else_token = input.parse::<Token![else]>()?;
+            let fallible = [&to, &to_ref, &to_mut, &to_owned]
+                .into_iter()
+                .flatten()
+                .any(|target| try_operand(target).is_some());
+            if !fallible {
+                return Err(syn::Error::new_spanned(
+                    else_token,
+                    "`else` requires a fallible target, e.g. `to self.conn.as_ref()?`.",
+                ));
+            }
+            Some(input.parse::<syn::Expr>()?)
+        } else {
+            None
+        };
+
+        let inner = if input.peek(keyword::inner) {
+            input.parse::<keyword::inner>()?;
+            input.parse::<Token![=]>()?;
+            Some(input.parse::<syn::Type>()?)
+        } else {
+            None
+        };
+
+        let wrap = if input.peek(keyword::wrap) {
+            input.parse::<keyword::wrap>()?;
+            input.parse::<Token![=]>()?;
+            Some(input.parse::<syn::Expr>()?)
+        } else {
+            None
+        };
+
+        let around = if input.peek(keyword::around) {
+            input.parse::<keyword::around>()?;
+            input.parse::<Token![=]>()?;
+            Some(input.parse::<syn::Path>()?)
+        } else {
+            None
+        };
 
-        let to = syn::Expr::parse_without_eager_brace(input)?;
+        let mut map_args = Vec::new();
+        let mut map_return = Vec::new();
+        loop {
+            if input.peek(template::keyword::map_args) {
+                input.parse::<template::keyword::map_args>()?;
+                map_args.push(input.parse::<Hook>()?);
+            } else if input.peek(template::keyword::map_return) {
+                let keyword = input.parse::<template::keyword::map_return>()?;
+                let hook = input.parse::<Hook>()?;
+                for selector in &hook.selector {
+                    if let Selector::Method { method, .. } = selector {
+                        return Err(syn::Error::new_spanned(
+                            method,
+                            format!(
+                                "`map_return` selects methods by name, expected `{}` without arguments.",
+                                method
+                            ),
+                        ));
+                    }
+                }
+                if hook.template.uses("arg") {
+                    return Err(syn::Error::new_spanned(
+                        keyword,
+                        "`$arg` is only available in `map_args` templates.",
+                    ));
+                }
+                map_return.push(hook);
+            } else {
+                break;
+            }
+        }
 
         let wh = if input.peek(Token![where]) {
             Some(input.parse::<WhereClause>().unwrap())
@@ -95,11 +444,241 @@ impl Parse for TraitConfig {
         };
 
         Ok(Self {
+            unsafety,
             path,
             generics,
+            self_crate,
             to,
+            bind,
+            via,
+            to_ref,
+            to_mut,
+            to_owned,
+            missing,
+            inner,
+            wrap,
+            around,
+            map_args,
+            map_return,
             wh,
             wi,
         })
     }
 }
+
+/// Parses `<keyword> = <expr>` if `K` comes next.
+fn parse_receiver_target<K: Parse + syn::token::Token>(
+    input: syn::parse::ParseStream,
+) -> syn::Result<Option<syn::Expr>> {
+    if !K::peek(input.cursor()) {
+        return Ok(None);
+    }
+    input.parse::<K>()?;
+    input.parse::<Token![=]>()?;
+    Ok(Some(syn::Expr::parse_without_eager_brace(input)?))
+}
+
+impl Parse for TraitDefaults {
+    fn parse(input: syn::parse::ParseStream) -> syn::Result<Self> {
+        let (to, bind) = if input.peek(keyword::to) {
+            input.parse::<keyword::to>()?;
+            let bind = input.parse::<Option<Token![fn]>>()?;
+            (Some(syn::Expr::parse_without_eager_brace(input)?), bind)
+        } else {
+            (None, None)
+        };
+
+        let wh = if input.peek(Token![where]) {
+            Some(input.parse::<WhereClause>()?)
+        } else {
+            None
+        };
+
+        Ok(Self { to, bind, wh })
+    }
+}
+
+impl ToTokens for TraitDefaults {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(to) = &self.to {
+            let bind = &self.bind;
+            tokens.extend(quote! { to #bind #to });
+        }
+        self.wh.to_tokens(tokens);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn target(config: TokenStream, sig: Signature) -> syn::Result<String> {
+        let config: TraitConfig = parse2(config).unwrap();
+        config.method_target(&sig).map(|target| target.to_string())
+    }
+
+    #[test]
+    fn returned_types() {
+        let returned = |output: ReturnType| returned(&output);
+        assert_eq!(
+            returned(parse_quote! { -> Result<u8, E> }),
+            Some(Returned::Result)
+        );
+        assert_eq!(
+            returned(parse_quote! { -> ::std::result::Result<u8, E> }),
+            Some(Returned::Result)
+        );
+        assert_eq!(
+            returned(parse_quote! { -> io::Result<u8> }),
+            Some(Returned::Result)
+        );
+        assert_eq!(
+            returned(parse_quote! { -> Option<u8> }),
+            Some(Returned::Option)
+        );
+        assert_eq!(
+            returned(parse_quote! { -> core::option::Option<u8> }),
+            Some(Returned::Option)
+        );
+        assert_eq!(returned(parse_quote! { -> my::Option<u8> }), None);
+        assert_eq!(returned(parse_quote! { -> std::result::Option<u8> }), None);
+        assert_eq!(returned(parse_quote! { -> <T as Tr>::Result }), None);
+        assert_eq!(returned(parse_quote! { -> u8 }), None);
+        assert_eq!(returned(parse_quote! {}), None);
+    }
+
+    #[test]
+    fn fallible_option() {
+        assert_eq!(
+            target(
+                quote! { Store to self.inner.as_ref()? },
+                parse_quote! { fn get(&self) -> Option<u8> }
+            )
+            .unwrap(),
+            quote! { ::core::iter::IntoIterator::into_iter(self.inner.as_ref()).next()? }
+                .to_string()
+        );
+    }
+
+    #[test]
+    fn fallible_result() {
+        assert_eq!(
+            target(
+                quote! { Store to self.inner.as_ref()? else Error::Missing },
+                parse_quote! { fn get(&self) -> Result<u8, Error> }
+            )
+            .unwrap(),
+            ":: core :: iter :: IntoIterator :: into_iter (self . inner . as_ref ()) . next () . ok_or_else (| | Error :: Missing) ?"
+        );
+    }
+
+    #[test]
+    fn fallible_borrowed() {
+        assert_eq!(
+            target(
+                quote! { Store to &(self.conn()?) else Error::Missing },
+                parse_quote! { fn get(&self) -> Result<u8, Error> }
+            )
+            .unwrap(),
+            quote! { &(::core::iter::IntoIterator::into_iter(self.conn()).next().ok_or_else(|| Error::Missing)?) }
+                .to_string()
+                .replace("||", "| |")
+        );
+        let err = target(
+            quote! { Store to &self.conn()? },
+            parse_quote! { fn get(&self) -> u8 },
+        )
+        .unwrap_err();
+        assert!(err.to_string().starts_with("Method get returns `u8`"));
+    }
+
+    #[test]
+    fn fallible_result_without_else() {
+        let err = target(
+            quote! { Store to self.inner.as_ref()? },
+            parse_quote! { fn get(&self) -> Result<u8, Error> },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method get returns `Result`, expected `else <error>` after the targets to return when the fallible target is missing."
+        );
+    }
+
+    #[test]
+    fn fallible_other() {
+        let err = target(
+            quote! { Store to self.inner.as_ref()? },
+            parse_quote! { fn get(&self) -> my::Option<u8> },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method get returns `my :: Option < u8 >`, only methods returning `Result` or `Option` can use a fallible target."
+        );
+    }
+
+    #[test]
+    fn map_return_selectors() {
+        let err = parse2::<TraitConfig>(quote! { Store to self.inner map_return(get(key)) = $ })
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "`map_return` selects methods by name, expected `get` without arguments."
+        );
+        let err = parse2::<TraitConfig>(quote! { Store to self.inner map_return = ($arg, $) })
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "`$arg` is only available in `map_args` templates."
+        );
+    }
+
+    #[test]
+    fn bound_target_via() {
+        let config: TraitConfig = parse2(quote! { Store to fn self.inner via lock() }).unwrap();
+        let bound = |sig: Signature| {
+            let bound = config.bound_target(&sig).unwrap().unwrap().to_string();
+            (bound, config.method_target(&sig).unwrap().to_string())
+        };
+        assert_eq!(
+            bound(parse_quote! { fn get(&self) -> u8 }),
+            (
+                quote! { let __delegate_target = &(self.inner); }.to_string(),
+                quote! { &*((*__delegate_target)).lock().unwrap() }.to_string()
+            )
+        );
+        assert_eq!(
+            bound(parse_quote! { fn set(&mut self) }).0,
+            quote! { let __delegate_target = &mut (self.inner); }.to_string()
+        );
+    }
+
+    #[test]
+    fn bound_target() {
+        let config: TraitConfig =
+            parse2(quote! { Store to fn self.inner.lock().unwrap() }).unwrap();
+        let sig: Signature = parse_quote! { fn set(&mut self) };
+        assert_eq!(
+            config.bound_target(&sig).unwrap().unwrap().to_string(),
+            quote! {
+                #[allow(unused_mut)]
+                let mut __delegate_target = self.inner.lock().unwrap();
+            }
+            .to_string()
+        );
+        assert_eq!(
+            config.method_target(&sig).unwrap().to_string(),
+            quote! { &mut *__delegate_target }.to_string()
+        );
+        let sig: Signature = parse_quote! { fn get(&self) };
+        assert_eq!(
+            config.method_target(&sig).unwrap().to_string(),
+            quote! { &*__delegate_target }.to_string()
+        );
+    }
+}
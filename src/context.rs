@@ -1,27 +1,259 @@
+use std::borrow::Cow;
+
 use proc_macro2::TokenStream;
-use quote::quote;
-use syn::DeriveInput;
+use quote::{quote, ToTokens};
+use syn::punctuated::Punctuated;
+use syn::visit::{self, Visit};
+use syn::{
+    parse_quote, Data, DeriveInput, Expr, ExprField, ExprParen, ExprReference, ExprUnary, Fields,
+    Generics, Ident, Macro, Member, Token, Type, UnOp,
+};
 
 pub struct Context<'a> {
-    pub ident: &'a syn::Ident,
-    pub generics: &'a syn::Generics,
+    /// Type the trait is implemented for, e.g. `Wrapper<T>`.
+    pub self_ty: Type,
+    pub generics: Cow<'a, Generics>,
+    /// Fields or variants of the type, when built from its definition.
+    pub data: Option<&'a Data>,
+    ident: Option<&'a Ident>,
 }
 
 impl<'a> Context<'a> {
     pub fn new(input: &'a DeriveInput) -> Self {
+        let ident = &input.ident;
+        let (_, ty_generics, _) = input.generics.split_for_impl();
+        Self {
+            self_ty: parse_quote! { #ident #ty_generics },
+            generics: Cow::Borrowed(&input.generics),
+            data: Some(&input.data),
+            ident: Some(&input.ident),
+        }
+    }
+
+    /// Context of an impl for `self_ty`, generic over `generics`.
+    pub fn for_type(self_ty: Type, generics: Generics) -> Self {
         Self {
-            ident: &input.ident,
-            generics: &input.generics,
+            self_ty,
+            generics: Cow::Owned(generics),
+            data: None,
+            ident: None,
         }
     }
 
+    /// Name of the type, when built from its definition.
+    #[deprecated(note = "use `self_ty`, which also covers impls for types without a definition")]
+    pub fn ident(&self) -> Option<&'a Ident> {
+        self.ident
+    }
+
     pub fn in_impl(&self, trait_for: &TokenStream, tokens: &TokenStream) -> TokenStream {
-        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
-        let ident = self.ident;
+        let (impl_generics, _, where_clause) = self.generics.split_for_impl();
+        let self_ty = &self.self_ty;
         quote! {
-            impl #impl_generics #trait_for #ident #ty_generics #where_clause {
+            impl #impl_generics #trait_for #self_ty #where_clause {
                 #tokens
             }
         }
     }
+
+    /// Checks a target expression against the definition of the type: unions can only be read
+    /// in an `unsafe { ... }` target, unit structs have nothing to delegate to, and the fields of
+    /// structs and unions accessed on `self` must exist. Enums are left to the compiler.
+    pub fn check_target(&self, target: &Expr) -> syn::Result<()> {
+        let Some(data) = self.data else {
+            return Ok(());
+        };
+        let self_ty = self.self_ty.to_token_stream();
+        let fields = match data {
+            Data::Union(data) => {
+                let mut expr = target;
+                while let Expr::Reference(ExprReference { expr: inner, .. })
+                | Expr::Paren(ExprParen { expr: inner, .. })
+                | Expr::Try(syn::ExprTry { expr: inner, .. }) = expr
+                {
+                    expr = inner;
+                }
+                if !matches!(expr, Expr::Unsafe(_)) {
+                    return Err(syn::Error::new_spanned(
+                        target,
+                        format!(
+                            "`{}` is a union, expected an `unsafe {{ ... }}` target reading its field.",
+                            self_ty
+                        ),
+                    ));
+                }
+                Fields::Named(data.fields.clone())
+            }
+            Data::Struct(data) if matches!(data.fields, Fields::Unit) => {
+                return Err(syn::Error::new_spanned(
+                    target,
+                    format!(
+                        "`{}` is a unit struct, it has no field to delegate to.",
+                        self_ty
+                    ),
+                ))
+            }
+            Data::Struct(data) => data.fields.clone(),
+            Data::Enum(_) => return Ok(()),
+        };
+
+        let mut check = FieldCheck {
+            fields: &fields,
+            self_ty: &self_ty,
+            error: None,
+        };
+        check.visit_expr(target);
+        check.error.map_or(Ok(()), Err)
+    }
+}
+
+/// Checks that the fields accessed on `self`, e.g. `self.field`, `self.0` or `(*self).field`, are
+/// in `fields`. Method calls such as `self.backend()` are left to the compiler, and macros are
+/// checked when their arguments are expressions.
+struct FieldCheck<'a> {
+    fields: &'a Fields,
+    self_ty: &'a TokenStream,
+    error: Option<syn::Error>,
+}
+
+impl<'ast> Visit<'ast> for FieldCheck<'_> {
+    fn visit_expr_field(&mut self, expr: &'ast ExprField) {
+        if self.error.is_none() && is_self(&expr.base) {
+            let exists = match &expr.member {
+                Member::Named(name) => self
+                    .fields
+                    .iter()
+                    .any(|field| field.ident.as_ref() == Some(name)),
+                Member::Unnamed(index) => {
+                    matches!(self.fields, Fields::Unnamed(_))
+                        && (index.index as usize) < self.fields.len()
+                }
+            };
+            if !exists {
+                self.error = Some(syn::Error::new_spanned(
+                    &expr.member,
+                    format!(
+                        "No field `{}` in `{}`.",
+                        expr.member.to_token_stream(),
+                        self.self_ty
+                    ),
+                ));
+            }
+        }
+        visit::visit_expr_field(self, expr);
+    }
+
+    fn visit_macro(&mut self, mac: &'ast Macro) {
+        if let Ok(args) = mac.parse_body_with(Punctuated::<Expr, Token![,]>::parse_terminated) {
+            for arg in &args {
+                self.visit_expr(arg);
+            }
+        }
+    }
+}
+
+/// Whether `expr` is `self`, possibly dereferenced, borrowed or in parentheses.
+fn is_self(expr: &Expr) -> bool {
+    match expr {
+        Expr::Path(path) => path.qself.is_none() && path.path.is_ident("self"),
+        Expr::Paren(ExprParen { expr, .. })
+        | Expr::Reference(ExprReference { expr, .. })
+        | Expr::Unary(ExprUnary {
+            op: UnOp::Deref(_),
+            expr,
+            ..
+        }) => is_self(expr),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(input: DeriveInput, target: Expr) -> Result<(), String> {
+        Context::new(&input)
+            .check_target(&target)
+            .map_err(|err| err.to_string())
+    }
+
+    #[test]
+    fn struct_fields() {
+        let input: DeriveInput = parse_quote! { struct Wrapper { inner: Mem } };
+        assert_eq!(check(input.clone(), parse_quote! { &self.inner }), Ok(()));
+        assert_eq!(
+            check(input.clone(), parse_quote! { &(*self).inner }),
+            Ok(())
+        );
+        assert_eq!(
+            check(input.clone(), parse_quote! { self.backend() }),
+            Ok(())
+        );
+        assert_eq!(
+            check(input.clone(), parse_quote! { &(*self).other }),
+            Err("No field `other` in `Wrapper`.".to_string())
+        );
+        assert_eq!(
+            check(input.clone(), parse_quote! { self.inner.get(self.other) }),
+            Err("No field `other` in `Wrapper`.".to_string())
+        );
+        assert_eq!(
+            check(input, parse_quote! { identity!(&self.other) }),
+            Err("No field `other` in `Wrapper`.".to_string())
+        );
+    }
+
+    #[test]
+    fn tuple_struct_fields() {
+        let input: DeriveInput = parse_quote! { struct Wrapper<T>((Mem, T)); };
+        assert_eq!(check(input.clone(), parse_quote! { &self.0.1 }), Ok(()));
+        assert_eq!(
+            check(input.clone(), parse_quote! { &self.1 }),
+            Err("No field `1` in `Wrapper < T >`.".to_string())
+        );
+        assert_eq!(
+            check(input, parse_quote! { &self.inner }),
+            Err("No field `inner` in `Wrapper < T >`.".to_string())
+        );
+    }
+
+    #[test]
+    fn enums() {
+        let input: DeriveInput = parse_quote! { enum Either { Left(Mem), Right(Mem) } };
+        let target = parse_quote! {
+            match self {
+                Self::Left(inner) | Self::Right(inner) => inner,
+            }
+        };
+        assert_eq!(check(input, target), Ok(()));
+    }
+
+    #[test]
+    fn unions() {
+        let input: DeriveInput = parse_quote! { union Raw { inner: ManuallyDrop<Mem> } };
+        assert_eq!(
+            check(input.clone(), parse_quote! { unsafe { &*self.inner } }),
+            Ok(())
+        );
+        assert_eq!(
+            check(input.clone(), parse_quote! { &*self.inner }),
+            Err(
+                "`Raw` is a union, expected an `unsafe { ... }` target reading its field."
+                    .to_string()
+            )
+        );
+        assert_eq!(
+            check(input, parse_quote! { unsafe { &*self.other } }),
+            Err("No field `other` in `Raw`.".to_string())
+        );
+    }
+
+    #[test]
+    fn unit_structs() {
+        let input: DeriveInput = parse_quote! { struct Unit; };
+        assert_eq!(
+            check(input, parse_quote! { &self }),
+            Err("`Unit` is a unit struct, it has no field to delegate to.".to_string())
+        );
+    }
 }
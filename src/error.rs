@@ -0,0 +1,253 @@
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+/// Error raised while generating a crate from an INPUT file.
+#[derive(Debug)]
+pub enum BuildError {
+    Env {
+        var: &'static str,
+    },
+    Read {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Write {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+    Parse {
+        path: PathBuf,
+        text: String,
+        error: syn::Error,
+    },
+    /// The generated crate is not valid Rust, which is a bug of delegate-trait.
+    Generate {
+        error: syn::Error,
+    },
+    /// Invalid INPUT.toml. `line` is 0 when the error is not tied to a line, e.g. missing keys.
+    Config {
+        path: PathBuf,
+        line: usize,
+        key: Option<String>,
+        message: String,
+    },
+}
+
+impl BuildError {
+    /// Reports the error to cargo, one `cargo:warning=` line per diagnostic.
+    pub fn emit(&self) {
+        for line in self.to_string().lines() {
+            println!("cargo:warning={}", line);
+        }
+    }
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Env { var } => write!(f, "Could not read env var `{}`.", var),
+            Self::Read { path, source } => {
+                write!(f, "Could not read {}: {}.", path.display(), source)
+            }
+            Self::Write { path, source } => {
+                write!(f, "Could not write {}: {}.", path.display(), source)
+            }
+            Self::Generate { error } => write!(
+                f,
+                "Could not format the generated code: {}. Please report this bug.",
+                error
+            ),
+            Self::Parse { path, text, error } => {
+                for (index, error) in error.clone().into_iter().enumerate() {
+                    if index > 0 {
+                        writeln!(f)?;
+                    }
+                    write_excerpt(f, path, text, &error)?;
+                }
+                Ok(())
+            }
+            Self::Config {
+                path,
+                line,
+                key,
+                message,
+            } => {
+                match key {
+                    Some(key) => writeln!(f, "error: `{}`: {}", key, message)?,
+                    None => writeln!(f, "error: {}", message)?,
+                }
+                if *line == 0 {
+                    write!(f, " --> {}", path.display())
+                } else {
+                    write!(f, " --> {}:{}", path.display(), line)
+                }
+            }
+        }
+    }
+}
+
+impl std::error::Error for BuildError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Env { .. } | Self::Config { .. } => None,
+            Self::Read { source, .. } | Self::Write { source, .. } => Some(source),
+            Self::Parse { error, .. } | Self::Generate { error } => Some(error),
+        }
+    }
+}
+
+/// Renders `error` the way rustc does, with the location in INPUT and the offending line.
+fn write_excerpt(
+    f: &mut fmt::Formatter<'_>,
+    path: &Path,
+    text: &str,
+    error: &syn::Error,
+) -> fmt::Result {
+    writeln!(f, "error: {}", error)?;
+
+    let span = error.span();
+    // Errors without location (e.g. missing items) carry the call site span, which has no source
+    // text. Spans of another file, e.g. a registry this one extends, do not match `text`.
+    let located = span
+        .source_text()
+        .is_some_and(|source| text.get(span.byte_range()) == Some(source.as_str()));
+    if !located {
+        return write!(f, " --> {}", path.display());
+    }
+
+    let start = span.start();
+    let end = span.end();
+    let line = text.lines().nth(start.line - 1).unwrap_or_default();
+    let end_column = if end.line == start.line {
+        end.column
+    } else {
+        line.chars().count()
+    };
+
+    let number = start.line.to_string();
+    let gutter = " ".repeat(number.len());
+    writeln!(
+        f,
+        "{}--> {}:{}:{}",
+        gutter,
+        path.display(),
+        start.line,
+        start.column + 1
+    )?;
+    writeln!(f, "{} |", gutter)?;
+    writeln!(f, "{} | {}", number, line)?;
+    write!(
+        f,
+        "{} | {}{}",
+        gutter,
+        " ".repeat(start.column),
+        "^".repeat(end_column.saturating_sub(start.column).max(1))
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::{Span, TokenStream, TokenTree};
+
+    use super::*;
+
+    fn render(text: &str, error: impl FnOnce(Vec<TokenTree>) -> syn::Error) -> String {
+        let tokens = text.parse::<TokenStream>().unwrap().into_iter().collect();
+        BuildError::Parse {
+            path: PathBuf::from("INPUT"),
+            text: text.to_string(),
+            error: error(tokens),
+        }
+        .to_string()
+    }
+
+    #[test]
+    fn excerpt() {
+        let text = "crate_ident = a;\nmacro_ident = 1;\n";
+        let rendered = render(text, |tokens| {
+            syn::Error::new(tokens[6].span(), "expected identifier")
+        });
+        assert_eq!(
+            rendered,
+            "error: expected identifier\n \
+             --> INPUT:2:15\n  \
+             |\n\
+             2 | macro_ident = 1;\n  \
+             |               ^"
+        );
+    }
+
+    #[test]
+    fn excerpt_at_file_start() {
+        let rendered = render("crate_ident = a;", |tokens| {
+            syn::Error::new(tokens[0].span(), "unknown key")
+        });
+        assert_eq!(
+            rendered,
+            "error: unknown key\n \
+             --> INPUT:1:1\n  \
+             |\n\
+             1 | crate_ident = a;\n  \
+             | ^^^^^^^^^^^"
+        );
+    }
+
+    #[test]
+    fn excerpt_spanning_lines() {
+        let rendered = render("traits = {\n};", |tokens| {
+            syn::Error::new(tokens[2].span(), "expected a trait")
+        });
+        assert_eq!(
+            rendered,
+            "error: expected a trait\n \
+             --> INPUT:1:10\n  \
+             |\n\
+             1 | traits = {\n  \
+             |          ^"
+        );
+    }
+
+    #[test]
+    fn call_site() {
+        let rendered = render("crate_ident = a;", |_| {
+            syn::Error::new(Span::call_site(), "missing `macro_ident`")
+        });
+        assert_eq!(rendered, "error: missing `macro_ident`\n --> INPUT");
+    }
+
+    #[test]
+    fn span_of_another_file() {
+        let other = "macro_ident = b;".parse::<TokenStream>().unwrap();
+        let span = other.into_iter().next().unwrap().span();
+        let rendered = render("crate_ident = a;", |_| syn::Error::new(span, "unknown key"));
+        assert_eq!(rendered, "error: unknown key\n --> INPUT");
+    }
+
+    #[test]
+    fn first_specified_here() {
+        let text = "crate_ident = a;\ncrate_ident = b;\n";
+        let tokens = text.parse::<TokenStream>().unwrap();
+        let Err(error) = syn::parse2::<crate::input::DelegateInput>(tokens) else {
+            panic!("expected a duplicate key error");
+        };
+        let rendered = BuildError::Parse {
+            path: PathBuf::from("INPUT"),
+            text: text.to_string(),
+            error,
+        }
+        .to_string();
+        assert_eq!(
+            rendered,
+            "error: Already specified.\n \
+             --> INPUT:2:1\n  \
+             |\n\
+             2 | crate_ident = b;\n  \
+             | ^^^^^^^^^^^\n\
+             error: First specified here.\n \
+             --> INPUT:1:1\n  \
+             |\n\
+             1 | crate_ident = a;\n  \
+             | ^^^^^^^^^^^"
+        );
+    }
+}
@@ -0,0 +1,285 @@
+use std::path::{Path, PathBuf};
+
+use quote::ToTokens;
+use syn::parse::{Parse, ParseStream};
+use syn::{Ident, LitStr};
+
+use crate::error::BuildError;
+use crate::input::{parse_input_visiting, DelegateInput, TraitEntry};
+
+/// Registry whose traits are imported with `extends = ...;`.
+#[derive(Clone)]
+pub enum Extends {
+    /// Registry located through the `DEP_<NAME>_INPUT` env var. Cargo sets it when the crate
+    /// running the build depends on a crate declaring `links = "<name>"` whose build script is
+    /// one of the `build_delegate` macros.
+    Registry(Ident),
+    /// INPUT file, relative to the extending one.
+    Path(LitStr),
+}
+
+impl Extends {
+    fn locate(&self, base: &Path) -> syn::Result<PathBuf> {
+        match self {
+            Self::Registry(ident) => {
+                let var = format!("DEP_{}_INPUT", ident.to_string().to_uppercase());
+                std::env::var_os(&var).map(PathBuf::from).ok_or_else(|| {
+                    syn::Error::new_spanned(
+                        ident,
+                        format!(
+                            "Unknown registry {}: env var `{}` is not set. The registry crate must declare `links = \"{}\"` and be a dependency of this crate.",
+                            ident, var, ident
+                        ),
+                    )
+                })
+            }
+            Self::Path(lit) => Ok(base
+                .parent()
+                .unwrap_or_else(|| Path::new("."))
+                .join(lit.value())),
+        }
+    }
+}
+
+impl Parse for Extends {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        if input.peek(LitStr) {
+            Ok(Self::Path(input.parse()?))
+        } else {
+            Ok(Self::Registry(input.parse()?))
+        }
+    }
+}
+
+impl ToTokens for Extends {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        match self {
+            Self::Registry(ident) => ident.to_tokens(tokens),
+            Self::Path(lit) => lit.to_tokens(tokens),
+        }
+    }
+}
+
+/// Adds the traits of every extended registry to each macro of `input`.
+///
+/// Errors located in the extended registries are returned as `BuildError`s against their own
+/// files, errors about `input` itself as the inner `syn::Error`.
+pub(crate) fn resolve_extends(
+    path: &Path,
+    input: &mut DelegateInput,
+    visited: &mut Vec<PathBuf>,
+) -> Result<syn::Result<()>, BuildError> {
+    if input.extends.is_empty() {
+        return Ok(Ok(()));
+    }
+
+    let canonical = path.canonicalize().unwrap_or_else(|_| path.to_owned());
+    visited.push(canonical);
+    let imported = import(path, std::mem::take(&mut input.extends), visited);
+    visited.pop();
+    let imported = match imported? {
+        Ok(imported) => imported,
+        Err(err) => return Ok(Err(err)),
+    };
+
+    for macro_input in &mut input.macros {
+        for entry in &macro_input.traits {
+            if let Some((extends, _)) = imported
+                .iter()
+                .find(|(_, imported)| trait_name(imported) == trait_name(entry))
+            {
+                let mut err = syn::Error::new_spanned(
+                    &entry.item.path,
+                    format!(
+                        "Trait {} is also registered by an extended registry.",
+                        trait_name(entry)
+                    ),
+                );
+                err.combine(syn::Error::new_spanned(extends, "Imported here."));
+                return Ok(Err(err));
+            }
+        }
+        macro_input
+            .traits
+            .extend(imported.iter().map(|(_, entry)| entry.clone()));
+    }
+
+    Ok(Ok(()))
+}
+
+/// Parses the registries of `extends`, and returns their traits along with the entry importing
+/// them.
+fn import(
+    path: &Path,
+    extends: Vec<Extends>,
+    visited: &mut Vec<PathBuf>,
+) -> Result<syn::Result<Vec<(Extends, TraitEntry)>>, BuildError> {
+    let mut imported = Vec::<(Extends, TraitEntry)>::new();
+    for extends in extends {
+        let other_path = match extends.locate(path) {
+            Ok(other_path) => other_path,
+            Err(err) => return Ok(Err(err)),
+        };
+        println!("cargo:rerun-if-changed={}", other_path.display());
+
+        let other_canonical = other_path
+            .canonicalize()
+            .unwrap_or_else(|_| other_path.clone());
+        if visited.contains(&other_canonical) {
+            return Ok(Err(syn::Error::new_spanned(
+                &extends,
+                format!("Cyclic `extends` through {}.", other_path.display()),
+            )));
+        }
+
+        let other = parse_input_visiting(&other_path, visited, Ok)?;
+        for entry in other.macros.into_iter().flat_map(|m| m.traits) {
+            if let Some((first, _)) = imported
+                .iter()
+                .find(|(_, imported)| trait_name(imported) == trait_name(&entry))
+            {
+                let mut err = syn::Error::new_spanned(
+                    &extends,
+                    format!("Trait {} is already imported.", trait_name(&entry)),
+                );
+                err.combine(syn::Error::new_spanned(first, "First imported here."));
+                return Ok(Err(err));
+            }
+            imported.push((extends.clone(), entry));
+        }
+    }
+    Ok(Ok(imported))
+}
+
+fn trait_name(entry: &TraitEntry) -> &Ident {
+    &entry
+        .item
+        .path
+        .segments
+        .last()
+        .expect("trait_name: expected ident")
+        .ident
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn traits(input: &DelegateInput) -> Vec<String> {
+        input.macros[0]
+            .traits
+            .iter()
+            .map(|entry| trait_name(entry).to_string())
+            .collect()
+    }
+
+    fn message(err: BuildError) -> String {
+        match err {
+            BuildError::Parse { error, .. } => error.to_string(),
+            BuildError::Config { message, .. } => message,
+            err => panic!("unexpected error {}", err),
+        }
+    }
+
+    const SHARED: &str = "macro_ident = shared; traits = { trait my_crate::Shared {} };";
+
+    #[test]
+    fn path() {
+        let dir = TestDir::with_files(
+            &[
+                (
+                    "INPUT",
+                    "macro_ident = delegate; traits = { trait my_crate::Own {} }; extends = \"shared/INPUT\";",
+                ),
+                ("shared/INPUT", SHARED),
+            ],
+        );
+        let input = crate::parse_input(dir.join("INPUT")).unwrap();
+        assert_eq!(traits(&input), ["Own", "Shared"]);
+    }
+
+    #[test]
+    fn toml_path_named_like_a_registry() {
+        let dir = TestDir::with_files(&[
+            (
+                "INPUT.toml",
+                "macro_ident = \"delegate\"\n\
+                     extends = [{ path = \"INPUT\" }]\n\
+                     [[traits]]\n\
+                     definition = \"trait my_crate::Own {}\"\n",
+            ),
+            ("INPUT", SHARED),
+        ]);
+        let input = crate::parse_input(dir.join("INPUT.toml")).unwrap();
+        assert_eq!(traits(&input), ["Own", "Shared"]);
+    }
+
+    #[test]
+    fn toml_registry() {
+        let dir = TestDir::with_files(&[(
+            "INPUT.toml",
+            "macro_ident = \"delegate\"\n\
+                 extends = [{ registry = \"missing_registry\" }]\n\
+                 [[traits]]\n\
+                 definition = \"trait my_crate::Own {}\"\n",
+        )]);
+        let err = crate::parse_input(dir.join("INPUT.toml")).err().unwrap();
+        assert!(message(err).contains("`DEP_MISSING_REGISTRY_INPUT`"));
+    }
+
+    #[test]
+    fn toml_string() {
+        let dir = TestDir::with_files(&[(
+            "INPUT.toml",
+            "macro_ident = \"delegate\"\n\
+                 extends = [\"INPUT\"]\n\
+                 [[traits]]\n\
+                 definition = \"trait my_crate::Own {}\"\n",
+        )]);
+        let err = crate::parse_input(dir.join("INPUT.toml")).err().unwrap();
+        assert_eq!(
+            message(err),
+            "Expected `{ registry = \"...\" }` or `{ path = \"...\" }`, got string."
+        );
+    }
+
+    #[test]
+    fn cycle() {
+        let dir = TestDir::with_files(&[
+            (
+                "a/INPUT",
+                "macro_ident = a; traits = { trait my_crate::A {} }; extends = \"../b/INPUT\";",
+            ),
+            (
+                "b/INPUT",
+                "macro_ident = b; traits = { trait my_crate::B {} }; extends = \"../a/INPUT\";",
+            ),
+        ]);
+        let mut visited = Vec::new();
+        let err = parse_input_visiting(&dir.join("a/INPUT"), &mut visited, Ok)
+            .err()
+            .unwrap();
+        assert!(message(err).starts_with("Cyclic `extends` through "));
+        assert!(visited.is_empty());
+    }
+
+    #[test]
+    fn error_in_extended_registry() {
+        let dir = TestDir::with_files(
+            &[
+                (
+                    "INPUT",
+                    "macro_ident = delegate; traits = { trait my_crate::Own {} }; extends = \"shared/INPUT\";",
+                ),
+                ("shared/INPUT", "macro_ident = shared;"),
+            ],
+        );
+        let mut visited = Vec::new();
+        let err = parse_input_visiting(&dir.join("INPUT"), &mut visited, Ok)
+            .err()
+            .unwrap();
+        assert!(matches!(&err, BuildError::Parse { path, .. } if path.ends_with("shared/INPUT")));
+        assert!(visited.is_empty());
+    }
+}
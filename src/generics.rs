@@ -23,7 +23,7 @@ impl From<&GenericParam> for GenericIdent {
         match generic_param {
             GenericParam::Lifetime(l) => Self::Lifetime(l.lifetime.ident.clone()),
             GenericParam::Type(t) => Self::Other(t.ident.clone()),
-            GenericParam::Const(c) => Self::Other(c.ident.clone().clone()),
+            GenericParam::Const(c) => Self::Other(c.ident.clone()),
         }
     }
 }
@@ -89,7 +89,7 @@ pub fn merge_where_clauses(
     for predicate in w2.predicates.iter() {
         w1.predicates.push(predicate.clone());
         if !keep_where_token {
-            w1.where_token = w2.where_token.clone();
+            w1.where_token = w2.where_token;
         }
     }
 }
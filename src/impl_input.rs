@@ -0,0 +1,92 @@
+//! Input of the function-like macro implementing a registered trait for an explicit type, e.g.
+//! `delegate_impl!(impl<T: Store> Store for Wrapper<T> to self.0)`.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parse2, Generics, Path, Token, Type};
+
+use crate::{Context, TraitConfig};
+
+pub struct ImplInput {
+    /// Generics of the impl, from `impl<...>`.
+    pub generics: Generics,
+    pub self_ty: Type,
+    pub config: TraitConfig,
+}
+
+impl ImplInput {
+    pub fn context(&self) -> Context<'static> {
+        Context::for_type(self.self_ty.clone(), self.generics.clone())
+    }
+}
+
+/// Parses `[impl<G>] [unsafe] Trait<A> for Type <TraitConfig arguments>`.
+impl Parse for ImplInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let generics = if input.peek(Token![impl]) {
+            input.parse::<Token![impl]>()?;
+            input.parse::<Generics>()?
+        } else {
+            Generics::default()
+        };
+
+        let unsafety = input.parse::<Option<Token![unsafe]>>()?;
+        let path = input.parse::<Path>()?;
+        input.parse::<Token![for]>()?;
+        let self_ty = input.parse::<Type>()?;
+        let rest = input.parse::<TokenStream>()?;
+
+        Ok(Self {
+            generics,
+            self_ty,
+            config: parse2::<TraitConfig>(quote! { #unsafety #path #rest })?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::*;
+
+    #[test]
+    fn concrete() {
+        let input = parse2::<ImplInput>(quote! { Store for Wrapper<Mem> to &self.0 }).unwrap();
+        assert!(input.generics.params.is_empty());
+        assert_eq!(
+            input.self_ty.to_token_stream().to_string(),
+            "Wrapper < Mem >"
+        );
+        assert_eq!(input.config.path.to_token_stream().to_string(), "Store");
+        assert!(input.config.to.is_some());
+    }
+
+    #[test]
+    fn generic() {
+        let input = parse2::<ImplInput>(quote! {
+            impl<T> unsafe Store<K> for Wrapper<T> to &self.0 where T: Store<K>
+        })
+        .unwrap();
+        assert_eq!(input.generics.to_token_stream().to_string(), "< T >");
+        assert!(input.config.unsafety.is_some());
+        assert_eq!(input.config.generics.to_token_stream().to_string(), "< K >");
+        assert!(input.config.wh.is_some());
+
+        let context = input.context();
+        assert_eq!(
+            context.self_ty.to_token_stream().to_string(),
+            "Wrapper < T >"
+        );
+        assert!(context.data.is_none());
+    }
+
+    #[test]
+    fn missing_for() {
+        let err = parse2::<ImplInput>(quote! { Store Wrapper to &self.0 })
+            .err()
+            .unwrap();
+        assert_eq!(err.to_string(), "expected `for`");
+    }
+}
@@ -1,5 +1,6 @@
+use std::collections::HashMap;
 use std::fs::read_to_string;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 use proc_macro2::{Ident, Span, TokenStream};
 use syn::parse::{Parse, ParseStream};
@@ -7,35 +8,207 @@ use syn::punctuated::Punctuated;
 use syn::token::PathSep;
 use syn::{braced, parse2, PathArguments, PathSegment, Token};
 
+use crate::error::BuildError;
+use crate::extends::{resolve_extends, Extends};
+use crate::input_toml::parse_toml_input;
 use crate::trait_path::ItemTraitPath;
+use crate::TraitDefaults;
 
-pub fn parse_input<P: AsRef<Path>>(path: P) -> syn::Result<DelegateInput> {
-    let file = read_to_string(path.as_ref()).expect(&format!("Could not open {:?}", path.as_ref()));
+pub fn parse_input<P: AsRef<Path>>(path: P) -> Result<DelegateInput, BuildError> {
+    parse_input_then(path, Ok)
+}
+
+/// Parses INPUT, or INPUT.toml when the file has a `.toml` extension, resolves its `extends`
+/// and runs `f` on it. Errors returned by `f` are reported against the INPUT file, like parse
+/// errors.
+pub(crate) fn parse_input_then<P, F, T>(path: P, f: F) -> Result<T, BuildError>
+where
+    P: AsRef<Path>,
+    F: FnOnce(DelegateInput) -> syn::Result<T>,
+{
+    parse_input_visiting(path.as_ref(), &mut Vec::new(), f)
+}
+
+/// `visited` holds the INPUT files currently being resolved, to detect cyclic `extends`.
+pub(crate) fn parse_input_visiting<F, T>(
+    path: &Path,
+    visited: &mut Vec<PathBuf>,
+    f: F,
+) -> Result<T, BuildError>
+where
+    F: FnOnce(DelegateInput) -> syn::Result<T>,
+{
+    let text = read_to_string(path).map_err(|source| BuildError::Read {
+        path: path.to_owned(),
+        source,
+    })?;
+
+    let is_toml = path
+        .extension()
+        .is_some_and(|extension| extension == "toml");
+    // Spans do not point into INPUT.toml, whose Rust snippets are parsed separately.
+    let parse_error = |text: String, error: syn::Error| {
+        if is_toml {
+            BuildError::Config {
+                path: path.to_owned(),
+                line: 0,
+                key: None,
+                message: error
+                    .into_iter()
+                    .map(|error| error.to_string())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            }
+        } else {
+            BuildError::Parse {
+                path: path.to_owned(),
+                text,
+                error,
+            }
+        }
+    };
 
-    let stream = file.parse::<TokenStream>()?;
+    let mut input = if is_toml {
+        parse_toml_input(path, &text)?
+    } else {
+        match text
+            .parse::<TokenStream>()
+            .map_err(|err| syn::Error::new(err.span(), err))
+            .and_then(parse2::<DelegateInput>)
+        {
+            Ok(input) => input,
+            Err(error) => return Err(parse_error(text, error)),
+        }
+    };
 
-    parse2::<DelegateInput>(stream)
+    if let Some(self_crate) = &input.self_crate {
+        for entry in input.macros.iter_mut().flat_map(|m| &mut m.traits) {
+            entry.self_crate = Some(self_crate.clone());
+        }
+    }
+
+    if let Err(error) = resolve_extends(path, &mut input, visited)? {
+        return Err(parse_error(text, error));
+    }
+
+    if let Some(macro_input) = input.macros.iter().find(|m| m.traits.is_empty()) {
+        let error = syn::Error::new_spanned(
+            &macro_input.macro_ident,
+            format!("No traits for macro `{}`.", macro_input.macro_ident),
+        );
+        return Err(parse_error(text, error));
+    }
+
+    if let Err(error) = input.check_derive_attr() {
+        return Err(parse_error(text, error));
+    }
+
+    f(input).map_err(|error| parse_error(text, error))
 }
 
 pub struct DelegateInput {
-    pub crate_ident: Ident,
-    pub crate_impl_ident: Ident,
+    /// Facade crate re-exporting the macro. When omitted, the proc-macro crate is used on its
+    /// own and callers reach `delegate` through `delegate_trait`.
+    pub crate_ident: Option<Ident>,
+    pub crate_impl_ident: Option<Ident>,
+    /// Crate defining the traits. Paths starting with it are resolved where the macro expands.
+    pub self_crate: Option<Ident>,
+    /// Derive macro accepting `#[delegate_to(...)]` attributes for the traits of every macro.
+    pub derive_ident: Option<Ident>,
+    /// Helper attribute of the derive macro, `delegate_to` by default.
+    pub derive_attr: Option<Ident>,
+    pub macros: Vec<MacroInput>,
+    /// Other registries whose traits are added to every macro. Emptied once resolved.
+    pub extends: Vec<Extends>,
+}
+
+/// One attribute macro of the registry and the traits it can delegate.
+pub struct MacroInput {
     pub macro_ident: Ident,
-    pub traits: Vec<ItemTraitPath>,
+    pub traits: Vec<TraitEntry>,
+}
+
+#[derive(Clone)]
+pub struct TraitEntry {
+    pub item: ItemTraitPath,
+    pub defaults: TraitDefaults,
+    /// `self_crate` of the registry declaring the trait.
+    pub self_crate: Option<Ident>,
+}
+
+impl From<ItemTraitPath> for TraitEntry {
+    fn from(item: ItemTraitPath) -> Self {
+        Self {
+            item,
+            defaults: TraitDefaults::default(),
+            self_crate: None,
+        }
+    }
 }
 
 impl DelegateInput {
+    pub fn is_single_crate(&self) -> bool {
+        self.crate_ident.is_none()
+    }
+
+    /// Helper attribute of the derive macro.
+    pub fn derive_attr(&self) -> Ident {
+        self.derive_attr
+            .clone()
+            .unwrap_or_else(|| Ident::new("delegate_to", Span::call_site()))
+    }
+
+    /// Checks that `derive_attr` comes with `derive_ident`, and differs from the attribute
+    /// macros, which would take it over.
+    fn check_derive_attr(&self) -> syn::Result<()> {
+        if self.derive_ident.is_none() {
+            return match &self.derive_attr {
+                Some(derive_attr) => Err(syn::Error::new_spanned(
+                    derive_attr,
+                    "`derive_attr` requires `derive_ident`.",
+                )),
+                None => Ok(()),
+            };
+        }
+        let derive_attr = self.derive_attr();
+        match self
+            .macros
+            .iter()
+            .find(|macro_input| macro_input.macro_ident == derive_attr)
+        {
+            Some(macro_input) => Err(syn::Error::new_spanned(
+                self.derive_attr.as_ref().unwrap_or(&macro_input.macro_ident),
+                format!(
+                    "`{}` is both the helper attribute of the derive macro and an attribute macro, expected another `derive_attr`.",
+                    derive_attr
+                ),
+            )),
+            None => Ok(()),
+        }
+    }
+
+    /// Path the generated impls reach delegate-trait through: the facade's `__private` module,
+    /// or `::delegate_trait` in single-crate mode. The generated macro resolves the latter to the
+    /// name the caller depends on delegate-trait with.
     pub fn root(&self) -> syn::Path {
         let mut segments = Punctuated::<PathSegment, PathSep>::new();
 
-        segments.push(PathSegment {
-            ident: self.crate_ident.clone(),
-            arguments: PathArguments::None,
-        });
-        segments.push(PathSegment {
-            ident: Ident::new("__private", Span::call_site()),
-            arguments: PathArguments::None,
-        });
+        match &self.crate_ident {
+            Some(crate_ident) => {
+                segments.push(PathSegment {
+                    ident: crate_ident.clone(),
+                    arguments: PathArguments::None,
+                });
+                segments.push(PathSegment {
+                    ident: Ident::new("__private", Span::call_site()),
+                    arguments: PathArguments::None,
+                });
+            }
+            None => segments.push(PathSegment {
+                ident: Ident::new("delegate_trait", Span::call_site()),
+                arguments: PathArguments::None,
+            }),
+        }
 
         syn::Path {
             leading_colon: Some(PathSep::default()),
@@ -48,70 +221,265 @@ impl Parse for DelegateInput {
     fn parse(input: ParseStream) -> syn::Result<Self> {
         let mut crate_ident = Option::<Ident>::None;
         let mut crate_impl_ident = Option::<Ident>::None;
+        let mut self_crate = Option::<Ident>::None;
+        let mut derive_ident = Option::<Ident>::None;
+        let mut derive_attr = Option::<Ident>::None;
         let mut macro_ident = Option::<Ident>::None;
-        let mut traits = Option::<Vec<ItemTraitPath>>::None;
+        let mut traits = Option::<Vec<TraitEntry>>::None;
+        let mut macros = Option::<Vec<MacroInput>>::None;
+        let mut extends = Option::<Vec<Extends>>::None;
+
+        let mut specified = HashMap::<String, Ident>::new();
 
         while !input.is_empty() {
             let ident = input.parse::<Ident>()?;
+            if let Some(first) = specified.insert(ident.to_string(), ident.clone()) {
+                let mut err = syn::Error::new_spanned(&ident, "Already specified.");
+                err.combine(syn::Error::new_spanned(first, "First specified here."));
+                return Err(err);
+            }
             input.parse::<Token![=]>()?;
             match ident.to_string().as_str() {
                 "crate_ident" => {
-                    if crate_ident.is_some() {
-                        return Err(syn::Error::new_spanned(&ident, "Already specified."));
-                    }
                     crate_ident.replace(input.parse::<Ident>()?);
                 }
                 "crate_impl_ident" => {
-                    if crate_impl_ident.is_some() {
-                        return Err(syn::Error::new_spanned(&ident, "Already specified."));
-                    }
                     crate_impl_ident.replace(input.parse::<Ident>()?);
                 }
+                "self_crate" => {
+                    self_crate.replace(input.parse::<Ident>()?);
+                }
+                "derive_ident" => {
+                    derive_ident.replace(input.parse::<Ident>()?);
+                }
+                "derive_attr" => {
+                    derive_attr.replace(input.parse::<Ident>()?);
+                }
                 "macro_ident" => {
-                    if macro_ident.is_some() {
-                        return Err(syn::Error::new_spanned(&ident, "Already specified."));
-                    }
                     macro_ident.replace(input.parse::<Ident>()?);
                 }
                 "traits" => {
-                    if traits.is_some() {
-                        return Err(syn::Error::new_spanned(&ident, "Already specified."));
-                    }
-                    let content;
-                    braced!(content in input);
-                    let mut result = Vec::<ItemTraitPath>::new();
-                    while !content.is_empty() {
-                        result.push(content.parse::<ItemTraitPath>()?);
-                    }
-                    traits.replace(result);
+                    traits.replace(parse_traits(input)?);
+                }
+                "macros" => {
+                    macros.replace(parse_macros(input)?);
+                }
+                "extends" => {
+                    extends.replace(
+                        Punctuated::<Extends, Token![,]>::parse_separated_nonempty(input)?
+                            .into_iter()
+                            .collect(),
+                    );
                 }
                 _ => {
                     return Err(syn::Error::new_spanned(
                         &ident,
-                        &format!("Unknown identifier {}.", ident.to_string()),
+                        format!(
+                            "Unknown identifier {}, expected one of `crate_ident`, `crate_impl_ident`, `self_crate`, `derive_ident`, `derive_attr`, `macro_ident`, `traits`, `macros` or `extends`.",
+                            ident
+                        ),
                     ))
                 }
             }
             input.parse::<Token![;]>()?;
         }
 
+        let macros = match (macros, macro_ident, traits) {
+            (Some(macros), None, None) => macros,
+            (None, Some(macro_ident), Some(traits)) => vec![MacroInput {
+                macro_ident,
+                traits,
+            }],
+            (Some(_), _, _) => {
+                return Err(syn::Error::new_spanned(
+                    &specified["macros"],
+                    "`macros` cannot be combined with `macro_ident` and `traits`.",
+                ))
+            }
+            (None, None, _) => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "No item `macro_ident` specified.",
+                ))
+            }
+            (None, _, None) => {
+                return Err(syn::Error::new(
+                    Span::call_site(),
+                    "No item `traits` specified.",
+                ))
+            }
+        };
+
         Ok(Self {
-            crate_ident: crate_ident.ok_or(syn::Error::new(
-                Span::call_site(),
-                "No item `crate_ident_ident` specified.",
-            ))?,
-            crate_impl_ident: crate_impl_ident.ok_or(syn::Error::new(
-                Span::call_site(),
-                "No item `cate_impl_ident` specified.",
-            ))?,
-            macro_ident: macro_ident.ok_or(syn::Error::new(
-                Span::call_site(),
-                "No item `macro_ident` specified.",
-            ))?,
-            traits: traits.ok_or(syn::Error::new(
-                Span::call_site(),
-                "No item `trait` specified.",
-            ))?,
+            crate_ident,
+            crate_impl_ident,
+            self_crate,
+            derive_ident,
+            derive_attr,
+            macros,
+            extends: extends.unwrap_or_default(),
         })
     }
 }
+
+fn parse_traits(input: ParseStream) -> syn::Result<Vec<TraitEntry>> {
+    let content;
+    braced!(content in input);
+    let mut result = Vec::<TraitEntry>::new();
+    while !content.is_empty() {
+        result.push(content.parse::<ItemTraitPath>()?.into());
+    }
+    Ok(result)
+}
+
+/// Parses `{ macro_ident = { traits... }; ... }`.
+fn parse_macros(input: ParseStream) -> syn::Result<Vec<MacroInput>> {
+    let content;
+    let brace = braced!(content in input);
+    let mut result = Vec::<MacroInput>::new();
+    while !content.is_empty() {
+        let macro_ident = content.parse::<Ident>()?;
+        if let Some(first) = result
+            .iter()
+            .find(|macro_input| macro_input.macro_ident == macro_ident)
+        {
+            let mut err = syn::Error::new_spanned(&macro_ident, "Already specified.");
+            err.combine(syn::Error::new_spanned(
+                &first.macro_ident,
+                "First specified here.",
+            ));
+            return Err(err);
+        }
+        content.parse::<Token![=]>()?;
+        let traits = parse_traits(&content)?;
+        content.parse::<Token![;]>()?;
+        result.push(MacroInput {
+            macro_ident,
+            traits,
+        });
+    }
+    if result.is_empty() {
+        return Err(syn::Error::new(
+            brace.span.join(),
+            "Expected at least one macro.",
+        ));
+    }
+    Ok(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    /// Writes `text` to a fresh directory, as `name`, and parses it.
+    fn parse(name: &str, text: &str) -> Result<DelegateInput, BuildError> {
+        let dir = TestDir::with_files(&[(name, text)]);
+        parse_input(dir.join(name))
+    }
+
+    fn message(result: Result<DelegateInput, BuildError>) -> String {
+        match result {
+            Err(BuildError::Parse { error, .. }) => error.to_string(),
+            Err(BuildError::Config { message, .. }) => message,
+            Err(err) => panic!("unexpected error {}", err),
+            Ok(_) => panic!("expected an error"),
+        }
+    }
+
+    #[test]
+    fn macros() {
+        let input = parse(
+            "INPUT",
+            "macros = {
+                delegate_io = { trait std::io::Write { fn flush(&mut self) -> std::io::Result<()>; } };
+                delegate_store = { trait my_crate::Store { fn get(&self) -> u32; } };
+            };",
+        )
+        .unwrap();
+        let names = input
+            .macros
+            .iter()
+            .map(|m| (m.macro_ident.to_string(), m.traits.len()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                ("delegate_io".to_string(), 1),
+                ("delegate_store".to_string(), 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn empty_traits() {
+        assert_eq!(
+            message(parse("INPUT", "macro_ident = delegate; traits = {};")),
+            "No traits for macro `delegate`."
+        );
+        assert_eq!(
+            message(parse(
+                "INPUT",
+                "macros = { delegate = { trait my_crate::Store {} }; delegate_io = {}; };"
+            )),
+            "No traits for macro `delegate_io`."
+        );
+        assert_eq!(
+            message(parse("INPUT", "macros = {};")),
+            "Expected at least one macro."
+        );
+        assert_eq!(
+            message(parse(
+                "INPUT.toml",
+                "macro_ident = \"delegate\"\ntraits = []\n"
+            )),
+            "Expected at least one trait."
+        );
+    }
+
+    #[test]
+    fn derive_attr() {
+        let input = parse(
+            "INPUT",
+            "macro_ident = delegate; derive_ident = Delegate; traits = { trait a::T { fn f(&self); } };",
+        )
+        .unwrap();
+        assert_eq!(input.derive_attr(), "delegate_to");
+
+        let input = parse(
+            "INPUT",
+            "macro_ident = delegate; derive_ident = Delegate; derive_attr = forward; traits = { trait a::T { fn f(&self); } };",
+        )
+        .unwrap();
+        assert_eq!(input.derive_attr(), "forward");
+    }
+
+    #[test]
+    fn derive_attr_of_macro() {
+        assert_eq!(
+            message(parse(
+                "INPUT",
+                "macro_ident = delegate; derive_ident = Delegate; derive_attr = delegate; traits = { trait a::T { fn f(&self); } };",
+            )),
+            "`delegate` is both the helper attribute of the derive macro and an attribute macro, expected another `derive_attr`."
+        );
+        assert_eq!(
+            message(parse(
+                "INPUT",
+                "macro_ident = delegate_to; derive_ident = Delegate; traits = { trait a::T { fn f(&self); } };",
+            )),
+            "`delegate_to` is both the helper attribute of the derive macro and an attribute macro, expected another `derive_attr`."
+        );
+    }
+
+    #[test]
+    fn derive_attr_without_derive() {
+        assert_eq!(
+            message(parse(
+                "INPUT",
+                "macro_ident = delegate; derive_attr = forward; traits = { trait a::T { fn f(&self); } };",
+            )),
+            "`derive_attr` requires `derive_ident`."
+        );
+    }
+}
@@ -0,0 +1,501 @@
+//! INPUT.toml, the structured alternative to the token-based INPUT format.
+//!
+//! ```toml
+//! crate_ident = "my_crate"
+//! crate_impl_ident = "my_crate_impl"
+//! # Crate defining the traits, resolved wherever the macros expand.
+//! self_crate = "my_crate"
+//! macro_ident = "delegate"
+//! # Also accept `#[derive(Delegate)] #[delegate_to(Trait to ...)]`.
+//! derive_ident = "Delegate"
+//! # Helper attribute of the derive macro, `delegate_to` by default.
+//! derive_attr = "delegate_to"
+//! # Registries (see `links`) or other INPUT files, relative to this one.
+//! extends = [{ registry = "other_registry" }, { path = "../shared/INPUT.toml" }]
+//!
+//! [[traits]]
+//! source = "traits/store.rs"
+//! to = "&self.inner"
+//!
+//! # Exposed through a second attribute macro instead of the root `macro_ident`, and only
+//! # registered with the `cache` feature.
+//! [[traits]]
+//! macro_ident = "delegate_cache"
+//! cfg = 'feature = "cache"'
+//! definition = """
+//! use my_crate::cache::Key;
+//!
+//! trait my_crate::Cache {
+//!     fn clear(&self);
+//!     fn evict(&self, key: Key);
+//! }
+//! """
+//! where = "Self: Sized"
+//! ```
+
+use std::fs::read_to_string;
+use std::path::Path;
+
+use proc_macro2::{Span, TokenStream};
+use syn::parse::{ParseStream, Parser};
+use syn::{parse2, parse_str, Ident, LitStr, Token, WhereClause};
+
+use crate::error::BuildError;
+use crate::extends::Extends;
+use crate::input::{DelegateInput, MacroInput, TraitEntry};
+use crate::toml::{self, Entry, Table, Value};
+use crate::trait_path::ItemTraitPath;
+use crate::TraitDefaults;
+
+const ROOT_KEYS: &[&str] = &[
+    "crate_ident",
+    "crate_impl_ident",
+    "self_crate",
+    "derive_ident",
+    "derive_attr",
+    "macro_ident",
+    "traits",
+    "extends",
+];
+const EXTENDS_KEYS: &[&str] = &["registry", "path"];
+const TRAIT_KEYS: &[&str] = &["definition", "source", "cfg", "to", "where", "macro_ident"];
+
+pub(crate) fn parse_toml_input(path: &Path, text: &str) -> Result<DelegateInput, BuildError> {
+    let loader = Loader { path };
+
+    let root = toml::parse(text).map_err(|err| loader.error(err.line, None, err.message))?;
+    loader.check_keys(&root, ROOT_KEYS, "")?;
+
+    let default_macro_ident = loader.optional_ident(&root, "macro_ident")?;
+
+    let mut macros = Vec::<MacroInput>::new();
+    match root.get("traits") {
+        Some(entry) => match &entry.value {
+            Value::Array(tables) if tables.is_empty() => {
+                return Err(loader.error(
+                    entry.line,
+                    Some("traits".to_string()),
+                    "Expected at least one trait.",
+                ))
+            }
+            Value::Array(tables) => {
+                for (index, table) in tables.iter().enumerate() {
+                    let key = format!("traits[{}]", index);
+                    let Value::Table(table) = table else {
+                        return Err(loader.error(
+                            entry.line,
+                            Some(key),
+                            format!("Expected a table, got {}.", table.type_name()),
+                        ));
+                    };
+                    let macro_ident = loader
+                        .optional_ident(table, "macro_ident")
+                        .map_err(|err| loader.prefix_key(err, &key))?
+                        .or_else(|| default_macro_ident.clone())
+                        .ok_or_else(|| {
+                            loader.error(
+                                table.line,
+                                Some(format!("{}.macro_ident", key)),
+                                "Missing key, and no root `macro_ident` to fall back to.",
+                            )
+                        })?;
+                    let trait_entry = loader.trait_entry(table, &key)?;
+                    match macros
+                        .iter_mut()
+                        .find(|macro_input| macro_input.macro_ident == macro_ident)
+                    {
+                        Some(macro_input) => macro_input.traits.push(trait_entry),
+                        None => macros.push(MacroInput {
+                            macro_ident,
+                            traits: vec![trait_entry],
+                        }),
+                    }
+                }
+            }
+            value => {
+                return Err(loader.error(
+                    entry.line,
+                    Some("traits".to_string()),
+                    format!(
+                        "Expected an array of tables (`[[traits]]`), got {}.",
+                        value.type_name()
+                    ),
+                ))
+            }
+        },
+        None => return Err(loader.error(0, Some("traits".to_string()), "Missing key.")),
+    };
+
+    Ok(DelegateInput {
+        crate_ident: loader.optional_ident(&root, "crate_ident")?,
+        crate_impl_ident: loader.optional_ident(&root, "crate_impl_ident")?,
+        self_crate: loader.optional_ident(&root, "self_crate")?,
+        derive_ident: loader.optional_ident(&root, "derive_ident")?,
+        derive_attr: loader.optional_ident(&root, "derive_attr")?,
+        macros,
+        extends: loader.extends(&root)?,
+    })
+}
+
+struct Loader<'a> {
+    path: &'a Path,
+}
+
+impl Loader<'_> {
+    fn trait_entry(&self, table: &Table, key: &str) -> Result<TraitEntry, BuildError> {
+        self.check_keys(table, TRAIT_KEYS, key)?;
+
+        let mut item = match (table.get("definition"), table.get("source")) {
+            (Some(definition), None) => {
+                let key = format!("{}.definition", key);
+                let text = self.string(definition, &key)?;
+                parse_str::<ItemTraitPath>(text)
+                    .map_err(|err| self.snippet_error(definition, key, err))?
+            }
+            (None, Some(source)) => {
+                let key = format!("{}.source", key);
+                let relative = self.string(source, &key)?;
+                let path = self
+                    .path
+                    .parent()
+                    .unwrap_or_else(|| Path::new("."))
+                    .join(relative);
+                println!("cargo:rerun-if-changed={}", path.display());
+                let text = read_to_string(&path).map_err(|err| {
+                    self.error(
+                        source.line,
+                        Some(key),
+                        format!("Could not read {}: {}.", path.display(), err),
+                    )
+                })?;
+                text.parse::<TokenStream>()
+                    .map_err(|err| syn::Error::new(err.span(), err))
+                    .and_then(parse2::<ItemTraitPath>)
+                    .map_err(|error| BuildError::Parse { path, text, error })?
+            }
+            (Some(_), Some(source)) => {
+                return Err(self.error(
+                    source.line,
+                    Some(key.to_string()),
+                    "Expected only one of `definition` and `source`.",
+                ))
+            }
+            (None, None) => {
+                return Err(self.error(
+                    table.line,
+                    Some(key.to_string()),
+                    "Expected one of `definition` and `source`.",
+                ))
+            }
+        };
+
+        if let Some(entry) = table.get("cfg") {
+            let key = format!("{}.cfg", key);
+            let text = self.string(entry, &key)?;
+            let predicate =
+                parse_str::<syn::Meta>(text).map_err(|err| self.snippet_error(entry, key, err))?;
+            item.attrs.push(syn::parse_quote! { #[cfg(#predicate)] });
+        }
+
+        // `to = "fn self.backend()"` binds the target once per method, like `to fn` in macros.
+        let (to, bind) = match table.get("to") {
+            Some(entry) => {
+                let key = format!("{}.to", key);
+                let text = self.string(entry, &key)?;
+                let (bind, to) = (|input: ParseStream| {
+                    Ok((
+                        input.parse::<Option<Token![fn]>>()?,
+                        syn::Expr::parse_without_eager_brace(input)?,
+                    ))
+                })
+                .parse_str(text)
+                .map_err(|err| self.snippet_error(entry, key, err))?;
+                (Some(to), bind)
+            }
+            None => (None, None),
+        };
+
+        let wh = match table.get("where") {
+            Some(entry) => {
+                let key = format!("{}.where", key);
+                let text = self.string(entry, &key)?;
+                Some(
+                    parse_str::<WhereClause>(&format!("where {}", text))
+                        .map_err(|err| self.snippet_error(entry, key, err))?,
+                )
+            }
+            None => None,
+        };
+
+        Ok(TraitEntry {
+            item,
+            defaults: TraitDefaults { to, bind, wh },
+            self_crate: None,
+        })
+    }
+
+    fn extends(&self, table: &Table) -> Result<Vec<Extends>, BuildError> {
+        let Some(entry) = table.get("extends") else {
+            return Ok(Vec::new());
+        };
+        let Value::Array(values) = &entry.value else {
+            return Err(self.error(
+                entry.line,
+                Some("extends".to_string()),
+                format!("Expected an array, got {}.", entry.value.type_name()),
+            ));
+        };
+        values
+            .iter()
+            .enumerate()
+            .map(|(index, value)| {
+                let key = format!("extends[{}]", index);
+                let Value::Table(table) = value else {
+                    return Err(self.error(
+                        entry.line,
+                        Some(key),
+                        format!(
+                            "Expected `{{ registry = \"...\" }}` or `{{ path = \"...\" }}`, got {}.",
+                            value.type_name()
+                        ),
+                    ));
+                };
+                self.check_keys(table, EXTENDS_KEYS, &key)?;
+                match (table.get("registry"), table.get("path")) {
+                    (Some(registry), None) => Ok(Extends::Registry(
+                        self.ident(registry, &format!("{}.registry", key))?,
+                    )),
+                    (None, Some(path)) => {
+                        let path = self.string(path, &format!("{}.path", key))?;
+                        Ok(Extends::Path(LitStr::new(path, Span::call_site())))
+                    }
+                    _ => Err(self.error(
+                        entry.line,
+                        Some(key),
+                        "Expected exactly one of `registry` and `path`.",
+                    )),
+                }
+            })
+            .collect()
+    }
+
+    fn optional_ident(&self, table: &Table, key: &str) -> Result<Option<Ident>, BuildError> {
+        table
+            .get(key)
+            .map(|entry| self.ident(entry, key))
+            .transpose()
+    }
+
+    fn ident(&self, entry: &Entry, key: &str) -> Result<Ident, BuildError> {
+        let text = self.string(entry, key)?;
+        parse_str::<Ident>(text).map_err(|_| {
+            self.error(
+                entry.line,
+                Some(key.to_string()),
+                format!("Expected an identifier, got `{}`.", text),
+            )
+        })
+    }
+
+    fn string<'a>(&self, entry: &'a Entry, key: &str) -> Result<&'a str, BuildError> {
+        match &entry.value {
+            Value::String(s) => Ok(s),
+            value => Err(self.error(
+                entry.line,
+                Some(key.to_string()),
+                format!("Expected a string, got {}.", value.type_name()),
+            )),
+        }
+    }
+
+    fn check_keys(&self, table: &Table, known: &[&str], prefix: &str) -> Result<(), BuildError> {
+        for entry in &table.entries {
+            if !known.contains(&entry.key.as_str()) {
+                let key = if prefix.is_empty() {
+                    entry.key.clone()
+                } else {
+                    format!("{}.{}", prefix, entry.key)
+                };
+                return Err(self.error(
+                    entry.line,
+                    Some(key),
+                    format!(
+                        "Unknown key, expected one of {}.",
+                        known
+                            .iter()
+                            .map(|key| format!("`{}`", key))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Reports an error in the Rust snippet of `entry` on the line of the file it points to.
+    fn snippet_error(&self, entry: &Entry, key: String, err: syn::Error) -> BuildError {
+        let line = entry.line_in_value(err.span().start().line);
+        self.error(line, Some(key), err.to_string())
+    }
+
+    fn prefix_key(&self, mut err: BuildError, prefix: &str) -> BuildError {
+        if let BuildError::Config { key: Some(key), .. } = &mut err {
+            *key = format!("{}.{}", prefix, key);
+        }
+        err
+    }
+
+    fn error(&self, line: usize, key: Option<String>, message: impl Into<String>) -> BuildError {
+        BuildError::Config {
+            path: self.path.to_owned(),
+            line,
+            key,
+            message: message.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::*;
+
+    fn parse(text: &str) -> Result<DelegateInput, BuildError> {
+        parse_toml_input(Path::new("INPUT.toml"), text)
+    }
+
+    fn config_error(text: &str) -> (usize, Option<String>, String) {
+        match parse(text) {
+            Err(BuildError::Config {
+                line, key, message, ..
+            }) => (line, key, message),
+            Err(err) => panic!("expected a config error, got {}", err),
+            Ok(_) => panic!("expected a config error"),
+        }
+    }
+
+    #[test]
+    fn traits() {
+        let input = parse(concat!(
+            "crate_ident = \"my_crate\"\r\n",
+            "macro_ident = \"delegate\"\r\n",
+            "\r\n",
+            "[[traits]]\r\n",
+            "to = \"fn &self.inner\"\r\n",
+            "where = \"Self: Sized\"\r\n",
+            "definition = '''\r\n",
+            "trait my_crate::Store {\r\n",
+            "    fn get(&self) -> u32;\r\n",
+            "}\r\n",
+            "'''\r\n",
+            "\r\n",
+            "[[traits]]\r\n",
+            "macro_ident = \"delegate_cache\"\r\n",
+            "cfg = 'feature = \"cache\"'\r\n",
+            "definition = \"trait my_crate::Cache { fn clear(&self); }\"\r\n",
+        ))
+        .unwrap();
+        assert_eq!(input.crate_ident.unwrap(), "my_crate");
+        let macros = input
+            .macros
+            .iter()
+            .map(|macro_input| {
+                let traits = macro_input
+                    .traits
+                    .iter()
+                    .map(|entry| entry.item.path.to_token_stream().to_string())
+                    .collect::<Vec<_>>();
+                (macro_input.macro_ident.to_string(), traits)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            macros,
+            [
+                (
+                    "delegate".to_string(),
+                    vec!["my_crate :: Store".to_string()]
+                ),
+                (
+                    "delegate_cache".to_string(),
+                    vec!["my_crate :: Cache".to_string()]
+                ),
+            ]
+        );
+        let store = &input.macros[0].traits[0].defaults;
+        assert!(store.bind.is_some());
+        assert!(store.wh.is_some());
+        assert_eq!(input.macros[1].traits[0].item.attrs.len(), 1);
+    }
+
+    #[test]
+    fn definition_error_line() {
+        let (line, key, _) = config_error(concat!(
+            "macro_ident = \"delegate\"\n",
+            "[[traits]]\n",
+            "definition = \"\"\"\n",
+            "trait my_crate::Store {\n",
+            "    fn get(&self) -> ;\n",
+            "}\n",
+            "\"\"\"\n",
+        ));
+        assert_eq!(line, 5);
+        assert_eq!(key.as_deref(), Some("traits[0].definition"));
+    }
+
+    #[test]
+    fn escaped_definition_error_line() {
+        let (line, key, _) = config_error(concat!(
+            "macro_ident = \"delegate\"\n",
+            "[[traits]]\n",
+            "definition = \"trait my_crate::Store {\\n fn get(&self) -> ; }\"\n",
+        ));
+        assert_eq!(line, 3);
+        assert_eq!(key.as_deref(), Some("traits[0].definition"));
+    }
+
+    #[test]
+    fn duplicate_table() {
+        let (line, _, message) = config_error(concat!(
+            "macro_ident = \"delegate\"\n",
+            "[traits]\n",
+            "[traits]\n",
+        ));
+        assert_eq!(line, 3);
+        assert_eq!(message, "Duplicate key.");
+    }
+
+    #[test]
+    fn unknown_key() {
+        let (line, key, message) = config_error(concat!(
+            "macro_ident = \"delegate\"\n",
+            "[[traits]]\n",
+            "definition = \"trait my_crate::Store {}\"\n",
+            "target = \"&self.inner\"\n",
+        ));
+        assert_eq!(line, 4);
+        assert_eq!(key.as_deref(), Some("traits[0].target"));
+        assert!(message.starts_with("Unknown key"));
+    }
+
+    #[test]
+    fn wrong_type() {
+        let (line, key, message) = config_error(concat!(
+            "macro_ident = true\n",
+            "[[traits]]\n",
+            "definition = \"trait my_crate::Store {}\"\n",
+        ));
+        assert_eq!(line, 1);
+        assert_eq!(key.as_deref(), Some("macro_ident"));
+        assert_eq!(message, "Expected a string, got boolean.");
+    }
+
+    #[test]
+    fn missing_traits() {
+        let (line, key, message) = config_error("macro_ident = \"delegate\"\n");
+        assert_eq!(line, 0);
+        assert_eq!(key.as_deref(), Some("traits"));
+        assert_eq!(message, "Missing key.");
+    }
+}
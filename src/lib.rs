@@ -1,22 +1,51 @@
 pub mod builds;
 
+pub mod callback;
+
 mod config;
-pub use config::TraitConfig;
+pub use config::{TraitConfig, TraitDefaults};
 
 mod context;
 pub use context::Context;
 
+mod error;
+pub use error::BuildError;
+
 mod modifier;
 
+mod extends;
+
 mod generics;
 pub use generics::GenericIdent;
 
+mod impl_input;
+pub use impl_input::ImplInput;
+
 mod input;
 pub use input::parse_input;
 
+mod input_toml;
+
+mod pointer_impl;
+pub use pointer_impl::generate_pointer_impls;
+
 mod trait_path;
 pub use trait_path::ItemTraitPath;
 
+mod self_crate;
+pub use self_crate::{dependency_path, resolve_self_crate};
+
+mod target;
+pub use target::{Adapter, ReceiverKind, Via};
+
+mod template;
+pub use template::{Hook, Placeholders, Selector, Template};
+
+#[cfg(test)]
+mod test_dir;
+
+mod toml;
+
 mod trait_impl;
 pub use delegate::delegate;
 pub use trait_impl::{generate_trait_impl, generate_traits_match};
@@ -34,16 +34,17 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
             syn::Type::BareFn(type_bare_fn) => self.0.modify_type_bare_fn(type_bare_fn),
             syn::Type::Group(type_group) => self.0.modify_type_group(type_group),
             syn::Type::ImplTrait(type_impl_trait) => self.0.modify_type_impl_trait(type_impl_trait),
-            syn::Type::Infer(_) => todo!(),
-            syn::Type::Macro(_) => todo!(),
-            syn::Type::Never(_) => todo!(),
-            syn::Type::Paren(_) => todo!(),
+            syn::Type::Paren(type_paren) => self.0.modify_type_paren(type_paren),
             syn::Type::Path(type_path) => self.0.modify_type_path(type_path),
             syn::Type::Ptr(type_ptr) => self.0.modify_type_ptr(type_ptr),
             syn::Type::Reference(type_reference) => self.0.modify_type_reference(type_reference),
             syn::Type::Slice(type_slice) => self.0.modify_type_slice(type_slice),
-            syn::Type::TraitObject(_) => todo!(),
+            syn::Type::TraitObject(type_trait_object) => {
+                self.0.modify_type_trait_object(type_trait_object)
+            }
             syn::Type::Tuple(type_tuple) => self.0.modify_type_tuple(type_tuple),
+            // `_`, `!`, and macros whose tokens cannot be walked.
+            syn::Type::Infer(_) | syn::Type::Never(_) | syn::Type::Macro(_) => (),
             syn::Type::Verbatim(_) => (),
             _ => (),
         }
@@ -54,9 +55,9 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
     }
 
     fn modify_type_bare_fn(&mut self, item: &mut syn::TypeBareFn) {
-        item.lifetimes
-            .as_mut()
-            .map(|lifetimes| self.0.modify_bound_lifetimes(lifetimes));
+        if let Some(lifetimes) = item.lifetimes.as_mut() {
+            self.0.modify_bound_lifetimes(lifetimes);
+        }
         item.inputs
             .iter_mut()
             .for_each(|input| self.0.modify_bare_fn_arg(input));
@@ -70,14 +71,16 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
     }
 
     fn modify_bare_fn_arg(&mut self, item: &mut syn::BareFnArg) {
-        item.name
-            .as_mut()
-            .map(|(ident, _)| self.0.modify_ident(ident));
+        if let Some((ident, _)) = item.name.as_mut() {
+            self.0.modify_ident(ident);
+        }
         self.0.modify_type(&mut item.ty)
     }
 
     fn modify_type_path(&mut self, item: &mut syn::TypePath) {
-        item.qself.as_mut().map(|qself| self.0.modify_qself(qself));
+        if let Some(qself) = item.qself.as_mut() {
+            self.0.modify_qself(qself);
+        }
         self.0.modify_path(&mut item.path)
     }
 
@@ -111,9 +114,9 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
     }
 
     fn modify_type_reference(&mut self, item: &mut syn::TypeReference) {
-        item.lifetime
-            .as_mut()
-            .map(|lifetime| self.0.modify_lifetime(lifetime));
+        if let Some(lifetime) = item.lifetime.as_mut() {
+            self.0.modify_lifetime(lifetime);
+        }
         self.0.modify_type(&mut item.elem)
     }
 
@@ -176,18 +179,23 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
         match item {
             syn::GenericArgument::Lifetime(lfietime) => self.0.modify_lifetime(lfietime),
             syn::GenericArgument::Type(ty) => self.0.modify_type(ty),
-            syn::GenericArgument::Const(_) => todo!(),
             syn::GenericArgument::AssocType(assoc_type) => self.0.modify_assoc_type(assoc_type),
-            syn::GenericArgument::AssocConst(_) => todo!(),
-            syn::GenericArgument::Constraint(_) => todo!(),
+            syn::GenericArgument::AssocConst(assoc_const) => {
+                self.0.modify_ident(&mut assoc_const.ident);
+                if let Some(generics) = assoc_const.generics.as_mut() {
+                    self.0.modify_angle_bracketed_generic_argument(generics);
+                }
+            }
+            syn::GenericArgument::Constraint(constraint) => self.0.modify_constraint(constraint),
+            // Expressions are left as written.
+            syn::GenericArgument::Const(_) => (),
             _ => (),
         }
     }
 
     fn modify_return_type(&mut self, item: &mut syn::ReturnType) {
-        match item {
-            syn::ReturnType::Type(_, ty) => self.0.modify_type(ty),
-            _ => (),
+        if let syn::ReturnType::Type(_, ty) = item {
+            self.0.modify_type(ty)
         }
     }
 
@@ -203,7 +211,38 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
         item.params
             .iter_mut()
             .for_each(|param| self.0.modify_generic_param(param));
-        // todo: where clause
+        if let Some(where_clause) = item.where_clause.as_mut() {
+            self.0.modify_where_clause(where_clause);
+        }
+    }
+
+    fn modify_where_clause(&mut self, item: &mut syn::WhereClause) {
+        item.predicates
+            .iter_mut()
+            .for_each(|predicate| self.0.modify_where_predicate(predicate));
+    }
+
+    fn modify_where_predicate(&mut self, item: &mut syn::WherePredicate) {
+        match item {
+            syn::WherePredicate::Lifetime(predicate) => {
+                self.0.modify_lifetime(&mut predicate.lifetime);
+                predicate
+                    .bounds
+                    .iter_mut()
+                    .for_each(|bound| self.0.modify_lifetime(bound));
+            }
+            syn::WherePredicate::Type(predicate) => {
+                if let Some(lifetimes) = predicate.lifetimes.as_mut() {
+                    self.0.modify_bound_lifetimes(lifetimes);
+                }
+                self.0.modify_type(&mut predicate.bounded_ty);
+                predicate
+                    .bounds
+                    .iter_mut()
+                    .for_each(|bound| self.0.modify_type_param_bound(bound));
+            }
+            _ => (),
+        }
     }
 
     fn modify_ident(&mut self, _item: &mut syn::Ident) {}
@@ -217,15 +256,18 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
     }
 
     fn modify_trait_bound(&mut self, item: &mut syn::TraitBound) {
-        // todo: modifier
-        item.lifetimes
-            .as_mut()
-            .map(|lifetimes| self.0.modify_bound_lifetimes(lifetimes));
+        if let Some(lifetimes) = item.lifetimes.as_mut() {
+            self.0.modify_bound_lifetimes(lifetimes);
+        }
+        self.0.modify_path(&mut item.path);
     }
 
     fn modify_item_trait_path(&mut self, item: &mut crate::ItemTraitPath) {
         self.0.modify_path(&mut item.path);
         self.0.modify_generics(&mut item.generics);
+        item.supertraits
+            .iter_mut()
+            .for_each(|bound| self.0.modify_type_param_bound(bound));
         item.items
             .iter_mut()
             .for_each(|trait_item| self.0.modify_trait_item(trait_item));
@@ -248,23 +290,23 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
         item.bounds
             .iter_mut()
             .for_each(|bound| self.0.modify_type_param_bound(bound));
-        item.default
-            .as_mut()
-            .map(|(_, default)| self.0.modify_type(default));
+        if let Some((_, default)) = item.default.as_mut() {
+            self.0.modify_type(default);
+        }
     }
 
     fn modify_trait_item_const(&mut self, item: &mut syn::TraitItemConst) {
         self.0.modify_ident(&mut item.ident);
         self.0.modify_generics(&mut item.generics);
         self.0.modify_type(&mut item.ty);
-        // todo: default
+        // The default value is an expression, left as written.
     }
 
     fn modify_assoc_type(&mut self, item: &mut syn::AssocType) {
         self.0.modify_ident(&mut item.ident);
-        item.generics
-            .as_mut()
-            .map(|generics| self.0.modify_angle_bracketed_generic_argument(generics));
+        if let Some(generics) = item.generics.as_mut() {
+            self.0.modify_angle_bracketed_generic_argument(generics);
+        }
         self.0.modify_type(&mut item.ty);
     }
 
@@ -281,4 +323,24 @@ impl<'a, T: TokenModifier> TokenModifier for LookupTokenModifier<'a, T> {
     fn modify_type_slice(&mut self, item: &mut syn::TypeSlice) {
         self.0.modify_type(&mut item.elem)
     }
+
+    fn modify_type_paren(&mut self, item: &mut syn::TypeParen) {
+        self.0.modify_type(&mut item.elem)
+    }
+
+    fn modify_type_trait_object(&mut self, item: &mut syn::TypeTraitObject) {
+        item.bounds
+            .iter_mut()
+            .for_each(|bound| self.0.modify_type_param_bound(bound));
+    }
+
+    fn modify_constraint(&mut self, item: &mut syn::Constraint) {
+        self.0.modify_ident(&mut item.ident);
+        if let Some(generics) = item.generics.as_mut() {
+            self.0.modify_angle_bracketed_generic_argument(generics);
+        }
+        item.bounds
+            .iter_mut()
+            .for_each(|bound| self.0.modify_type_param_bound(bound));
+    }
 }
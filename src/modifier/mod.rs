@@ -7,6 +7,9 @@ pub use lookup::LookupTokenModifier;
 mod rename_path_root;
 pub use rename_path_root::PathRootRenamer;
 
+mod resolve_uses;
+pub use resolve_uses::UseResolver;
+
 pub trait TokenModifier: Sized {
     fn modify_trait_item_fn(&mut self, item: &mut syn::TraitItemFn) {
         LookupTokenModifier(self).modify_trait_item_fn(item);
@@ -126,6 +129,14 @@ pub trait TokenModifier: Sized {
         LookupTokenModifier(self).modify_generics(item)
     }
 
+    fn modify_where_clause(&mut self, item: &mut syn::WhereClause) {
+        LookupTokenModifier(self).modify_where_clause(item)
+    }
+
+    fn modify_where_predicate(&mut self, item: &mut syn::WherePredicate) {
+        LookupTokenModifier(self).modify_where_predicate(item)
+    }
+
     fn modify_ident(&mut self, item: &mut syn::Ident) {
         LookupTokenModifier(self).modify_ident(item)
     }
@@ -169,4 +180,16 @@ pub trait TokenModifier: Sized {
     fn modify_type_slice(&mut self, item: &mut syn::TypeSlice) {
         LookupTokenModifier(self).modify_type_slice(item)
     }
+
+    fn modify_type_paren(&mut self, item: &mut syn::TypeParen) {
+        LookupTokenModifier(self).modify_type_paren(item)
+    }
+
+    fn modify_type_trait_object(&mut self, item: &mut syn::TypeTraitObject) {
+        LookupTokenModifier(self).modify_type_trait_object(item)
+    }
+
+    fn modify_constraint(&mut self, item: &mut syn::Constraint) {
+        LookupTokenModifier(self).modify_constraint(item)
+    }
 }
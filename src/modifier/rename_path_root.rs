@@ -1,22 +1,20 @@
 use super::{LookupTokenModifier, TokenModifier};
 
-#[derive(Debug, Clone)]
+/// Replaces the first segment of paths named `original` with `rename`, e.g. `my_crate::Key`
+/// with `crate::Key` or `::renamed::Key`.
+#[derive(Clone)]
 pub struct PathRootRenamer {
     pub original: String,
-    pub rename: syn::Ident,
-    pub remove_leading_colon: bool,
+    pub rename: syn::Path,
 }
 
 impl TokenModifier for PathRootRenamer {
     fn modify_path(&mut self, item: &mut syn::Path) {
-        if item.segments.len() > 1 {
-            let first = item.segments.first_mut().unwrap();
-            if first.ident.to_string() == self.original {
-                first.ident = self.rename.clone();
-                if self.remove_leading_colon {
-                    item.leading_colon = None;
-                }
-            }
+        if item.segments.len() > 1 && item.segments[0].ident == self.original {
+            let rest = item.segments.iter().skip(1).cloned().collect::<Vec<_>>();
+            item.segments = self.rename.segments.clone();
+            item.segments.extend(rest);
+            item.leading_colon = self.rename.leading_colon;
         }
         LookupTokenModifier(self).modify_path(item);
     }
@@ -0,0 +1,187 @@
+use std::collections::HashMap;
+
+use syn::punctuated::Punctuated;
+use syn::{GenericParam, Generics, ItemUse, PathArguments, UseTree};
+
+use super::{LookupTokenModifier, TokenModifier};
+
+/// Qualifies the paths starting with a name imported by `use` declarations, e.g. `Key` into
+/// `my_crate::types::Key` after `use my_crate::types::Key;`. Generic parameters shadow imports.
+#[derive(Clone)]
+pub struct UseResolver {
+    imports: HashMap<String, syn::Path>,
+    shadowed: Vec<String>,
+}
+
+impl UseResolver {
+    pub fn new(uses: &[ItemUse], generics: &Generics) -> syn::Result<Self> {
+        let mut imports = HashMap::new();
+        for item in uses {
+            let prefix = syn::Path {
+                leading_colon: item.leading_colon,
+                segments: Punctuated::new(),
+            };
+            collect_imports(&item.tree, prefix, &mut imports)?;
+        }
+        Ok(Self {
+            imports,
+            shadowed: generic_names(generics).collect(),
+        })
+    }
+}
+
+fn collect_imports(
+    tree: &UseTree,
+    mut prefix: syn::Path,
+    imports: &mut HashMap<String, syn::Path>,
+) -> syn::Result<()> {
+    let (ident, name) = match tree {
+        UseTree::Path(path) => {
+            prefix.segments.push(path.ident.clone().into());
+            return collect_imports(&path.tree, prefix, imports);
+        }
+        UseTree::Group(group) => {
+            for tree in &group.items {
+                collect_imports(tree, prefix.clone(), imports)?;
+            }
+            return Ok(());
+        }
+        UseTree::Glob(glob) => {
+            return Err(syn::Error::new_spanned(
+                glob,
+                "Glob imports cannot be resolved, import items by name.",
+            ))
+        }
+        UseTree::Name(name) => (&name.ident, &name.ident),
+        UseTree::Rename(rename) => (&rename.ident, &rename.rename),
+    };
+
+    if ident == "self" {
+        if prefix.segments.is_empty() {
+            return Err(syn::Error::new_spanned(
+                ident,
+                "Expected a module before `self`.",
+            ));
+        }
+    } else {
+        prefix.segments.push(ident.clone().into());
+    }
+    let name = if name == "self" {
+        &prefix.segments.last().unwrap().ident
+    } else {
+        name
+    };
+    imports.insert(name.to_string(), prefix.clone());
+    Ok(())
+}
+
+fn generic_names(generics: &Generics) -> impl Iterator<Item = String> + '_ {
+    generics.params.iter().filter_map(|param| match param {
+        GenericParam::Type(param) => Some(param.ident.to_string()),
+        GenericParam::Const(param) => Some(param.ident.to_string()),
+        GenericParam::Lifetime(_) => None,
+    })
+}
+
+impl TokenModifier for UseResolver {
+    fn modify_signature(&mut self, item: &mut syn::Signature) {
+        let len = self.shadowed.len();
+        self.shadowed.extend(generic_names(&item.generics));
+        LookupTokenModifier(self).modify_signature(item);
+        self.shadowed.truncate(len);
+    }
+
+    fn modify_path(&mut self, item: &mut syn::Path) {
+        let first = &item.segments[0].ident;
+        if item.leading_colon.is_none() && !self.shadowed.iter().any(|name| first == name) {
+            if let Some(import) = self.imports.get(&first.to_string()) {
+                let mut segments = import.segments.clone();
+                let last = segments.last_mut().unwrap();
+                last.arguments =
+                    std::mem::replace(&mut item.segments[0].arguments, PathArguments::None);
+                segments.extend(item.segments.iter().skip(1).cloned());
+                item.segments = segments;
+                item.leading_colon = import.leading_colon;
+            }
+        }
+        LookupTokenModifier(self).modify_path(item);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use crate::ItemTraitPath;
+
+    fn resolve(definition: &str) -> String {
+        let mut item = syn::parse_str::<ItemTraitPath>(definition).unwrap();
+        item.resolve_uses().unwrap();
+        item.to_token_stream().to_string()
+    }
+
+    #[test]
+    fn signatures() {
+        let resolved = resolve(
+            "use a::Key;
+            trait a::Store {
+                fn get(&self, key: (Key)) -> [Key; 2];
+                fn never(&self, _: Key) -> !;
+            }",
+        );
+        assert!(resolved.contains("key : (a :: Key)"), "{}", resolved);
+        assert!(resolved.contains("[a :: Key ; 2]"), "{}", resolved);
+        assert!(resolved.contains("_ : a :: Key) -> !"), "{}", resolved);
+    }
+
+    #[test]
+    fn bounds() {
+        let resolved = resolve(
+            "use a::{Key, Base, Visit};
+            trait a::Store<T: Visit>: Base where T: Base {
+                fn conv<K: Into<Key>>(&self, key: K) -> Key where K: Clone + Base;
+                fn visit(&self, f: &dyn Fn(Key) -> Key);
+                fn each(&self) -> impl Iterator<Item: Visit>;
+            }",
+        );
+        assert!(resolved.contains("< T : a :: Visit >"), "{}", resolved);
+        assert!(
+            resolved.contains(": a :: Base where T : a :: Base"),
+            "{}",
+            resolved
+        );
+        assert!(resolved.contains("K : Into < a :: Key >"), "{}", resolved);
+        assert!(resolved.contains("K : Clone + a :: Base"), "{}", resolved);
+        assert!(
+            resolved.contains("dyn Fn (a :: Key) -> a :: Key"),
+            "{}",
+            resolved
+        );
+        assert!(resolved.contains("Item : a :: Visit"), "{}", resolved);
+    }
+
+    #[test]
+    fn generics_shadow_imports() {
+        let resolved = resolve(
+            "use a::Key;
+            trait a::Store {
+                fn get<Key: Clone>(&self, key: Key) -> Key;
+            }",
+        );
+        assert!(!resolved.contains("a :: Key"), "{}", resolved);
+    }
+
+    #[test]
+    fn left_as_written() {
+        let resolved = resolve(
+            "use a::Key;
+            trait a::Store {
+                const N: usize = 2;
+                fn get(&self) -> Buffer<{ Key::N }>;
+                fn made(&self) -> key!();
+            }",
+        );
+        assert!(resolved.contains("Buffer < { Key :: N } >"), "{}", resolved);
+        assert!(resolved.contains("-> key ! ()"), "{}", resolved);
+    }
+}
@@ -0,0 +1,193 @@
+//! Blanket impls of a registered trait for references and smart pointers, e.g.
+//! `impl<__T: Store + ?Sized> Store for Box<__T>`, forwarding every method to the pointee.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::{parse_quote, parse_str, FnArg, Generics, Path, ReturnType, TraitItem, Type};
+
+use crate::modifier::TokenModifier;
+use crate::target::ReceiverKind;
+use crate::trait_impl::{generate_trait_impl, generics_renamer, is_self, mentions_self};
+use crate::trait_path::ItemTraitPath;
+use crate::{Context, TraitConfig};
+
+struct Pointer {
+    ty: &'static str,
+    /// Receivers the pointee can be reached through.
+    kinds: &'static [ReceiverKind],
+    /// Constructor building the pointer back from the pointee, for functions returning `Self`.
+    wrap: Option<&'static str>,
+}
+
+const POINTERS: &[Pointer] = &[
+    Pointer {
+        ty: "&'__a __T",
+        kinds: &[ReceiverKind::Ref],
+        wrap: None,
+    },
+    Pointer {
+        ty: "&'__a mut __T",
+        kinds: &[ReceiverKind::Ref, ReceiverKind::Mut],
+        wrap: None,
+    },
+    Pointer {
+        ty: "::std::boxed::Box<__T>",
+        kinds: &[ReceiverKind::Ref, ReceiverKind::Mut, ReceiverKind::Owned],
+        wrap: Some("::std::boxed::Box::new"),
+    },
+    Pointer {
+        ty: "::std::rc::Rc<__T>",
+        kinds: &[ReceiverKind::Ref],
+        wrap: None,
+    },
+    Pointer {
+        ty: "::std::sync::Arc<__T>",
+        kinds: &[ReceiverKind::Ref],
+        wrap: None,
+    },
+];
+
+/// Implements the trait for `&T`, `&mut T`, `Box<T>`, `Rc<T>` and `Arc<T>` where `T`
+/// implements it, skipping the pointers that cannot reach the pointee the way some method
+/// needs, e.g. `&T` and `Arc<T>` when a method takes `&mut self`. Functions returning `Self` are
+/// only forwarded by `Box<T>`. Associated types and constants are those of `T`, and the pointers
+/// are required to implement the supertraits.
+pub fn generate_pointer_impls(
+    config: &TraitConfig,
+    root: Path,
+    trait_input: ItemTraitPath,
+) -> syn::Result<TokenStream> {
+    let mut kinds = Vec::<ReceiverKind>::new();
+    let mut returns_self = false;
+    for item in &trait_input.items {
+        let syn::TraitItem::Fn(method) = item else {
+            continue;
+        };
+        for input in &method.sig.inputs {
+            let kind = match input {
+                FnArg::Receiver(receiver) => ReceiverKind::of(receiver),
+                FnArg::Typed(input) => match &*input.ty {
+                    Type::Reference(reference) if is_self(&reference.elem) => {
+                        match reference.mutability {
+                            Some(_) => ReceiverKind::Mut,
+                            None => ReceiverKind::Ref,
+                        }
+                    }
+                    ty if mentions_self(ty) => ReceiverKind::Owned,
+                    _ => continue,
+                },
+            };
+            if !kinds.contains(&kind) {
+                kinds.push(kind);
+            }
+        }
+        if let ReturnType::Type(_, ty) = &method.sig.output {
+            returns_self |= mentions_self(ty);
+        }
+    }
+
+    let trait_path = &config.path;
+    let (_, trait_generics, _) = config.generics.split_for_impl();
+    let unsized_pointee = !kinds.contains(&ReceiverKind::Owned) && !returns_self;
+    let mut generics: Generics = if unsized_pointee {
+        parse_quote! { <__T: ?Sized> }
+    } else {
+        parse_quote! { <__T> }
+    };
+    generics.make_where_clause().predicates.push(parse_quote! {
+        __T: #trait_path #trait_generics
+    });
+
+    let (assoc_items, supertraits) = pointee_items(config, &trait_input)?;
+
+    let mut impls = TokenStream::new();
+    for pointer in POINTERS {
+        let reachable = kinds.iter().all(|kind| pointer.kinds.contains(kind));
+        if !reachable || (returns_self && pointer.wrap.is_none()) {
+            continue;
+        }
+
+        let self_ty = parse_str::<Type>(pointer.ty)?;
+        let mut generics = generics.clone();
+        if matches!(self_ty, Type::Reference(_)) {
+            generics.params.insert(0, parse_quote! { '__a });
+        }
+        if !supertraits.is_empty() {
+            generics.make_where_clause().predicates.push(parse_quote! {
+                #self_ty: #supertraits
+            });
+        }
+        let context = Context::for_type(self_ty, generics);
+
+        let mut config = config.clone();
+        config.to = None;
+        config.via = None;
+        config.to_ref = Some(parse_quote! { &**self });
+        config.to_mut = Some(parse_quote! { &mut **self });
+        config.to_owned = Some(parse_quote! { *self });
+        config.missing = None;
+        config.inner = Some(parse_quote! { __T });
+        config.wrap = pointer.wrap.map(parse_str).transpose()?;
+        let wi = config.wi.take();
+        config.wi = Some(quote! { #wi #assoc_items });
+
+        impls.extend(generate_trait_impl(
+            &context,
+            &config,
+            root.clone(),
+            trait_input.clone(),
+        )?);
+    }
+
+    Ok(impls)
+}
+
+/// Associated types and constants of the trait set to those of `__T`, e.g.
+/// `type Out = <__T as Trait>::Out;`, and the supertraits of the trait, with the generics of the
+/// definition renamed to the ones of `config`.
+fn pointee_items(
+    config: &TraitConfig,
+    trait_input: &ItemTraitPath,
+) -> syn::Result<(TokenStream, TokenStream)> {
+    let mut trait_input = trait_input.clone();
+    trait_input.resolve_uses()?;
+    let mut renamer = generics_renamer(&trait_input, config)?;
+
+    let trait_path = &config.path;
+    let (_, trait_generics, _) = config.generics.split_for_impl();
+    let mut items = TokenStream::new();
+    for item in &mut trait_input.items {
+        match item {
+            TraitItem::Type(ty) => {
+                renamer.modify_trait_item_type(ty);
+                let ident = &ty.ident;
+                let (impl_generics, ty_generics, where_clause) = ty.generics.split_for_impl();
+                let attrs = ty.attrs.iter().filter(|attr| attr.path().is_ident("cfg"));
+                items.extend(quote! {
+                    #(#attrs)*
+                    type #ident #impl_generics = <__T as #trait_path #trait_generics>::#ident #ty_generics #where_clause;
+                });
+            }
+            TraitItem::Const(constant) => {
+                renamer.modify_trait_item_const(constant);
+                let ident = &constant.ident;
+                let ty = &constant.ty;
+                let attrs = constant
+                    .attrs
+                    .iter()
+                    .filter(|attr| attr.path().is_ident("cfg"));
+                items.extend(quote! {
+                    #(#attrs)*
+                    const #ident: #ty = <__T as #trait_path #trait_generics>::#ident;
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let mut supertraits = trait_input.supertraits;
+    for bound in &mut supertraits {
+        renamer.modify_type_param_bound(bound);
+    }
+    Ok((items, quote! { #supertraits }))
+}
@@ -0,0 +1,371 @@
+//! Resolution of `self_crate`, the crate defining the traits of a registry, from the crate
+//! where a macro expands: `crate` within it, its dependency name elsewhere. This is what `$crate`
+//! does for `macro_rules!`, which proc macros have to work out from cargo's metadata.
+
+use std::fs::read_to_string;
+use std::path::{Path as FsPath, PathBuf};
+use std::sync::Mutex;
+
+use proc_macro2::Span;
+use syn::{Ident, Path};
+
+use crate::modifier::{PathRootRenamer, TokenModifier};
+use crate::toml::{self, Table, Value};
+use crate::trait_path::ItemTraitPath;
+use crate::TraitConfig;
+
+const DEPENDENCY_TABLES: &[&str] = &["dependencies", "dev-dependencies", "dev_dependencies"];
+
+/// Dependencies of a manifest, or why they could not be read.
+type Dependencies = Result<Vec<Dependency>, String>;
+
+/// Dependencies of the manifests read so far, by manifest directory. Every expansion of a crate
+/// looks them up, so the manifest is only read once per compilation.
+static DEPENDENCIES: Mutex<Vec<(PathBuf, Dependencies)>> = Mutex::new(Vec::new());
+
+/// Rewrites the paths of `trait_input` starting with `self_crate` so that they resolve from the
+/// crate being compiled, or from `config.self_crate` when the caller specifies it (e.g. after
+/// `extern crate my_crate as renamed;`). Imports of the definition are resolved first.
+///
+/// Registries without `self_crate` keep the behaviour they had before it existed: paths starting
+/// with the package being compiled resolve to `crate`, even from its binaries and tests. This
+/// fallback is deprecated, and the build of the proc-macro crate warns about it.
+pub fn resolve_self_crate(
+    trait_input: &mut ItemTraitPath,
+    self_crate: Option<&str>,
+    config: &TraitConfig,
+) -> syn::Result<()> {
+    trait_input.resolve_uses()?;
+
+    let mut renamer = match self_crate {
+        Some(self_crate) => PathRootRenamer {
+            original: self_crate.to_string(),
+            rename: match &config.self_crate {
+                Some(path) => path.clone(),
+                None => self_crate_path(self_crate)?,
+            },
+        },
+        None => match std::env::var("CARGO_PKG_NAME") {
+            Ok(package) => PathRootRenamer {
+                original: package.replace('-', "_"),
+                rename: syn::parse_quote! { crate },
+            },
+            Err(_) => return Ok(()),
+        },
+    };
+    renamer.modify_item_trait_path(trait_input);
+    Ok(())
+}
+
+fn self_crate_path(self_crate: &str) -> syn::Result<Path> {
+    // Binaries named like the library of their package share its crate name, but reach it as a
+    // dependency. Integration tests have crate names of their own.
+    let current = std::env::var("CARGO_CRATE_NAME").ok();
+    if current.as_deref() == Some(self_crate) && std::env::var_os("CARGO_BIN_NAME").is_none() {
+        return Ok(syn::parse_quote! { crate });
+    }
+
+    dependency_path(self_crate)
+}
+
+/// Path to the crate `name` as a dependency of the crate being compiled, e.g. `::renamed`.
+pub fn dependency_path(name: &str) -> syn::Result<Path> {
+    let renamed = match std::env::var_os("CARGO_MANIFEST_DIR") {
+        Some(manifest_dir) => cached_dependencies(FsPath::new(&manifest_dir))
+            .map_err(|message| syn::Error::new(Span::call_site(), message))?
+            .into_iter()
+            .find(|dependency| dependency.package == name)
+            .map(|dependency| dependency.name),
+        None => None,
+    };
+    let ident = Ident::new(renamed.as_deref().unwrap_or(name), Span::call_site());
+    Ok(syn::parse_quote! { ::#ident })
+}
+
+/// A dependency of the crate being compiled, with `-` replaced by `_`.
+#[derive(Debug, Clone, PartialEq)]
+struct Dependency {
+    /// Name the crate is reached with, e.g. `renamed` for `renamed = { package = "my-crate" }`.
+    name: String,
+    package: String,
+}
+
+fn cached_dependencies(manifest_dir: &FsPath) -> Dependencies {
+    let mut cache = DEPENDENCIES.lock().unwrap_or_else(|err| err.into_inner());
+    if let Some((_, dependencies)) = cache.iter().find(|(dir, _)| dir == manifest_dir) {
+        return dependencies.clone();
+    }
+    let dependencies = dependencies(manifest_dir);
+    cache.push((manifest_dir.to_owned(), dependencies.clone()));
+    dependencies
+}
+
+/// Dependencies declared in the manifest of `manifest_dir`, including the ones inherited from
+/// the workspace with `workspace = true`.
+fn dependencies(manifest_dir: &FsPath) -> Dependencies {
+    let root = read_manifest(&manifest_dir.join("Cargo.toml"))?;
+
+    let mut tables = vec![&root];
+    if let Some(Value::Table(targets)) = root.get("target").map(|entry| &entry.value) {
+        tables.extend(
+            targets
+                .entries
+                .iter()
+                .filter_map(|entry| match &entry.value {
+                    Value::Table(target) => Some(target),
+                    _ => None,
+                }),
+        );
+    }
+
+    let mut workspace = Option::<Table>::None;
+    let mut dependencies = Vec::new();
+    for table in tables
+        .into_iter()
+        .flat_map(|table| DEPENDENCY_TABLES.iter().filter_map(|key| table.get(key)))
+        .filter_map(|entry| match &entry.value {
+            Value::Table(dependencies) => Some(dependencies),
+            _ => None,
+        })
+    {
+        for entry in &table.entries {
+            let declaration = match &entry.value {
+                Value::Table(declaration) => Some(declaration),
+                _ => None,
+            };
+            let inherited = declaration.is_some_and(|declaration| {
+                matches!(
+                    declaration.get("workspace").map(|entry| &entry.value),
+                    Some(Value::Boolean(true))
+                )
+            });
+            let package = if inherited {
+                if workspace.is_none() {
+                    workspace = Some(workspace_dependencies(manifest_dir, &root)?);
+                }
+                workspace
+                    .as_ref()
+                    .and_then(|workspace| workspace.get(&entry.key))
+                    .and_then(|entry| match &entry.value {
+                        Value::Table(declaration) => package(declaration),
+                        _ => None,
+                    })
+            } else {
+                declaration.and_then(package)
+            };
+            dependencies.push(Dependency {
+                name: entry.key.replace('-', "_"),
+                package: package.unwrap_or(&entry.key).replace('-', "_"),
+            });
+        }
+    }
+    Ok(dependencies)
+}
+
+/// `package` of a dependency declaration, when renamed.
+fn package(declaration: &Table) -> Option<&str> {
+    match declaration.get("package").map(|entry| &entry.value) {
+        Some(Value::String(package)) => Some(package),
+        _ => None,
+    }
+}
+
+/// `[workspace.dependencies]` of the workspace of the package at `manifest_dir`: the one named
+/// by `package.workspace`, or the closest manifest with a `[workspace]` table.
+fn workspace_dependencies(manifest_dir: &FsPath, manifest: &Table) -> Result<Table, String> {
+    let explicit = manifest
+        .get("package")
+        .and_then(|entry| match &entry.value {
+            Value::Table(package) => package.get("workspace"),
+            _ => None,
+        })
+        .and_then(|entry| match &entry.value {
+            Value::String(path) => Some(manifest_dir.join(path)),
+            _ => None,
+        });
+
+    let workspace = match explicit {
+        Some(dir) => Some(read_manifest(&dir.join("Cargo.toml"))?),
+        None if manifest.get("workspace").is_some() => Some(manifest.clone()),
+        None => {
+            let mut found = None;
+            for dir in manifest_dir.ancestors().skip(1) {
+                let path = dir.join("Cargo.toml");
+                if path.is_file() {
+                    let candidate = read_manifest(&path)?;
+                    if candidate.get("workspace").is_some() {
+                        found = Some(candidate);
+                        break;
+                    }
+                }
+            }
+            found
+        }
+    };
+
+    Ok(workspace
+        .as_ref()
+        .and_then(|workspace| workspace.get("workspace"))
+        .and_then(|entry| match &entry.value {
+            Value::Table(workspace) => workspace.get("dependencies"),
+            _ => None,
+        })
+        .and_then(|entry| match &entry.value {
+            Value::Table(dependencies) => Some(dependencies.clone()),
+            _ => None,
+        })
+        .unwrap_or_default())
+}
+
+fn read_manifest(path: &FsPath) -> Result<Table, String> {
+    let text = read_to_string(path)
+        .map_err(|err| format!("Could not read {}: {}.", path.display(), err))?;
+    toml::parse(&text).map_err(|err| {
+        format!(
+            "Could not parse {}:{}: {}",
+            path.display(),
+            err.line,
+            err.message
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::*;
+    use crate::test_dir::TestDir;
+
+    fn dependency(name: &str, package: &str) -> Dependency {
+        Dependency {
+            name: name.to_string(),
+            package: package.to_string(),
+        }
+    }
+
+    #[test]
+    fn renamed_dependencies() {
+        let dir = TestDir::with_files(&[(
+            "Cargo.toml",
+            "[package]\n\
+                 name = \"caller\"\n\
+                 [dependencies]\n\
+                 plain = \"1\"\n\
+                 renamed = { package = \"my-crate\", version = \"1\" }\n\
+                 [target.'cfg(unix)'.dependencies]\n\
+                 unix-only = { package = \"other-crate\", path = \"../other\" }\n\
+                 [dev-dependencies]\n\
+                 dev-plain = \"1\"\n",
+        )]);
+        assert_eq!(
+            dependencies(&dir).unwrap(),
+            [
+                dependency("plain", "plain"),
+                dependency("renamed", "my_crate"),
+                dependency("dev_plain", "dev_plain"),
+                dependency("unix_only", "other_crate"),
+            ]
+        );
+    }
+
+    #[test]
+    fn workspace_renames() {
+        let dir = TestDir::with_files(&[
+            (
+                "Cargo.toml",
+                "[workspace]\n\
+                     members = [\"caller\"]\n\
+                     [workspace.dependencies]\n\
+                     renamed = { package = \"my-crate\", path = \"my-crate\" }\n\
+                     plain = \"1\"\n",
+            ),
+            (
+                "caller/Cargo.toml",
+                "[package]\n\
+                     name = \"caller\"\n\
+                     [dependencies]\n\
+                     renamed = { workspace = true }\n\
+                     plain.workspace = true\n",
+            ),
+        ]);
+        assert_eq!(
+            dependencies(&dir.join("caller")).unwrap(),
+            [
+                dependency("renamed", "my_crate"),
+                dependency("plain", "plain"),
+            ]
+        );
+    }
+
+    #[test]
+    fn explicit_workspace() {
+        let dir = TestDir::with_files(&[
+            (
+                "root/Cargo.toml",
+                "[workspace]\n\
+                     [workspace.dependencies]\n\
+                     renamed = { package = \"my-crate\" }\n",
+            ),
+            (
+                "caller/Cargo.toml",
+                "[package]\n\
+                     name = \"caller\"\n\
+                     workspace = \"../root\"\n\
+                     [dependencies]\n\
+                     renamed.workspace = true\n",
+            ),
+        ]);
+        assert_eq!(
+            dependencies(&dir.join("caller")).unwrap(),
+            [dependency("renamed", "my_crate")]
+        );
+    }
+
+    #[test]
+    fn invalid_manifest() {
+        let dir = TestDir::with_files(&[(
+            "Cargo.toml",
+            "[package]\nname = \"caller\"\nname = \"again\"\n",
+        )]);
+        let message = dependencies(&dir).unwrap_err();
+        assert!(message.starts_with("Could not parse "), "{}", message);
+        assert!(
+            message.ends_with("Cargo.toml:3: Duplicate key."),
+            "{}",
+            message
+        );
+    }
+
+    #[test]
+    fn missing_manifest() {
+        let dir = TestDir::with_files(&[]);
+        let message = dependencies(&dir).unwrap_err();
+        assert!(message.starts_with("Could not read "), "{}", message);
+    }
+
+    #[test]
+    fn legacy_package_rename() {
+        // `CARGO_PKG_NAME` is `delegate-trait` in the tests of this crate.
+        let mut trait_input = syn::parse_str::<ItemTraitPath>(
+            "trait delegate_trait::Store { fn get(&self) -> delegate_trait::Key; }",
+        )
+        .unwrap();
+        let config = syn::parse_str::<TraitConfig>("Store").unwrap();
+        resolve_self_crate(&mut trait_input, None, &config).unwrap();
+        let tokens = trait_input.to_token_stream().to_string();
+        assert!(tokens.contains("-> crate :: Key"), "{}", tokens);
+    }
+
+    #[test]
+    fn caller_self_crate() {
+        let mut trait_input = syn::parse_str::<ItemTraitPath>(
+            "trait my_crate::Store { fn get(&self) -> my_crate::Key; }",
+        )
+        .unwrap();
+        let config = syn::parse_str::<TraitConfig>("Store self_crate = ::renamed").unwrap();
+        resolve_self_crate(&mut trait_input, Some("my_crate"), &config).unwrap();
+        let tokens = trait_input.to_token_stream().to_string();
+        assert!(tokens.contains("-> :: renamed :: Key"), "{}", tokens);
+    }
+}
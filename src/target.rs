@@ -0,0 +1,170 @@
+//! Access to the delegation target, which depends on the receiver of each method.
+
+use proc_macro2::TokenStream;
+use quote::quote;
+use syn::parse::{Parse, ParseStream};
+use syn::{parenthesized, Expr, Ident, Receiver, Type};
+
+/// How a method takes `self`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReceiverKind {
+    /// `&self`, `self: &Self`.
+    Ref,
+    /// `&mut self`, `self: &mut Self`.
+    Mut,
+    /// `self`, and typed receivers such as `self: Box<Self>`.
+    Owned,
+}
+
+impl ReceiverKind {
+    pub fn of(receiver: &Receiver) -> Self {
+        match &*receiver.ty {
+            Type::Reference(reference) if reference.mutability.is_some() => Self::Mut,
+            Type::Reference(_) => Self::Ref,
+            _ => Self::Owned,
+        }
+    }
+
+    /// Key of the TraitConfig target specific to this kind.
+    pub fn key(self) -> &'static str {
+        match self {
+            Self::Ref => "ref",
+            Self::Mut => "mut",
+            Self::Owned => "owned",
+        }
+    }
+
+    pub fn describe(self) -> &'static str {
+        match self {
+            Self::Ref => "`&self`",
+            Self::Mut => "`&mut self`",
+            Self::Owned => "`self`",
+        }
+    }
+}
+
+/// Adapter reaching the target through a smart pointer or a lock, e.g. `via lock()`.
+#[derive(Clone)]
+pub struct Via {
+    pub ident: Ident,
+    pub adapter: Adapter,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Adapter {
+    /// `Mutex`, possibly behind `Arc`.
+    Lock,
+    /// `RwLock`, shared access only.
+    Read,
+    /// `RwLock`.
+    Write,
+    /// `RefCell`, shared access only.
+    Borrow,
+    /// `RefCell`.
+    BorrowMut,
+    /// `Box`, `Rc`, `Arc`, shared access only.
+    Deref,
+    /// `Box`, which also gives the target by value.
+    DerefMut,
+}
+
+impl Adapter {
+    const ALL: [Self; 7] = [
+        Self::Lock,
+        Self::Read,
+        Self::Write,
+        Self::Borrow,
+        Self::BorrowMut,
+        Self::Deref,
+        Self::DerefMut,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Lock => "lock",
+            Self::Read => "read",
+            Self::Write => "write",
+            Self::Borrow => "borrow",
+            Self::BorrowMut => "borrow_mut",
+            Self::Deref => "deref",
+            Self::DerefMut => "deref_mut",
+        }
+    }
+}
+
+impl Via {
+    /// Expression giving the target to methods taking `kind`, from the `to` expression.
+    /// Lock poisoning panics.
+    pub fn access(&self, to: &Expr, kind: ReceiverKind) -> syn::Result<TokenStream> {
+        use Adapter::*;
+        use ReceiverKind::*;
+
+        let access = match (self.adapter, kind) {
+            (Lock, Ref) => quote! { &*(#to).lock().unwrap() },
+            (Lock, Mut) => quote! { &mut *(#to).lock().unwrap() },
+            (Read | Write, Ref) => quote! { &*(#to).read().unwrap() },
+            (Write, Mut) => quote! { &mut *(#to).write().unwrap() },
+            (Borrow | BorrowMut, Ref) => quote! { &*(#to).borrow() },
+            (BorrowMut, Mut) => quote! { &mut *(#to).borrow_mut() },
+            (Deref | DerefMut, Ref) => quote! { &*(#to) },
+            (DerefMut, Mut) => quote! { &mut *(#to) },
+            (DerefMut, Owned) => quote! { *(#to) },
+            (adapter @ (Read | Borrow | Deref), Mut) => {
+                let hint = match adapter {
+                    Read => "write()",
+                    Borrow => "borrow_mut()",
+                    _ => "deref_mut()",
+                };
+                return Err(syn::Error::new_spanned(
+                    &self.ident,
+                    format!(
+                        "`via {}()` only gives shared access, which methods taking {} cannot use, expected `via {}`.",
+                        adapter.name(),
+                        kind.describe(),
+                        hint
+                    ),
+                ));
+            }
+            (adapter, Owned) => {
+                return Err(syn::Error::new_spanned(
+                    &self.ident,
+                    format!(
+                        "`via {}()` cannot move the target out, which methods taking {} need.",
+                        adapter.name(),
+                        kind.describe()
+                    ),
+                ))
+            }
+        };
+        Ok(access)
+    }
+}
+
+impl Parse for Via {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let ident = input.parse::<Ident>()?;
+        let adapter = Adapter::ALL
+            .into_iter()
+            .find(|adapter| ident == adapter.name())
+            .ok_or_else(|| {
+                syn::Error::new_spanned(
+                    &ident,
+                    format!(
+                        "Unknown adapter {}, expected one of {}.",
+                        ident,
+                        Adapter::ALL
+                            .iter()
+                            .map(|adapter| format!("`{}()`", adapter.name()))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                )
+            })?;
+        let content;
+        parenthesized!(content in input);
+        if !content.is_empty() {
+            return Err(content.error("Expected no arguments."));
+        }
+        Ok(Self { ident, adapter })
+    }
+}
@@ -0,0 +1,315 @@
+//! `map_args` and `map_return` hooks, whose templates are expanded for each forwarded method.
+
+use proc_macro2::{Group, TokenStream, TokenTree};
+use quote::{quote, ToTokens};
+use syn::parse::{Parse, ParseStream};
+use syn::punctuated::Punctuated;
+use syn::{parenthesized, Ident, LitStr, Token};
+
+/// Expression where `$` stands for the value being mapped, `$method` for the name of the method
+/// as a string literal, `$arg` for the name of the argument as a string literal and `$ty` for the
+/// type of the argument or the return type, e.g. `$.map_err(|err| Error::new($method, err))`.
+#[derive(Clone)]
+pub struct Template {
+    tokens: TokenStream,
+}
+
+/// What the placeholders of a template stand for.
+pub struct Placeholders<'a> {
+    pub value: &'a TokenStream,
+    pub method: &'a Ident,
+    /// Name of the argument, for `map_args`.
+    pub arg: Option<&'a Ident>,
+    pub ty: TokenStream,
+}
+
+impl Template {
+    pub fn expand(&self, placeholders: &Placeholders) -> TokenStream {
+        expand(self.tokens.clone(), placeholders)
+    }
+
+    /// Whether the template uses `$<name>`.
+    pub fn uses(&self, name: &str) -> bool {
+        uses(self.tokens.clone(), name)
+    }
+}
+
+fn expand(tokens: TokenStream, placeholders: &Placeholders) -> TokenStream {
+    let name = |ident: &Ident| LitStr::new(&ident.to_string(), ident.span()).to_token_stream();
+    let mut result = TokenStream::new();
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                let expanded = match tokens.peek() {
+                    Some(TokenTree::Ident(ident)) if ident == "method" => {
+                        Some(name(placeholders.method))
+                    }
+                    Some(TokenTree::Ident(ident)) if ident == "arg" => placeholders.arg.map(name),
+                    Some(TokenTree::Ident(ident)) if ident == "ty" => Some(placeholders.ty.clone()),
+                    _ => None,
+                };
+                match expanded {
+                    Some(expanded) => {
+                        tokens.next();
+                        result.extend(expanded);
+                    }
+                    None => {
+                        let value = placeholders.value;
+                        result.extend(quote! { (#value) });
+                    }
+                }
+            }
+            TokenTree::Group(group) => {
+                let mut expanded =
+                    Group::new(group.delimiter(), expand(group.stream(), placeholders));
+                expanded.set_span(group.span());
+                result.extend([TokenTree::Group(expanded)]);
+            }
+            token => result.extend([token]),
+        }
+    }
+    result
+}
+
+fn uses(tokens: TokenStream, name: &str) -> bool {
+    let mut tokens = tokens.into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '$' => {
+                if matches!(tokens.peek(), Some(TokenTree::Ident(ident)) if ident == name) {
+                    return true;
+                }
+            }
+            TokenTree::Group(group) if uses(group.stream(), name) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+/// Parses the template up to the next TraitConfig clause.
+impl Parse for Template {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let mut tokens = TokenStream::new();
+        while !input.is_empty() && !is_clause_start(input) {
+            tokens.extend([input.parse::<TokenTree>()?]);
+        }
+
+        // Check that the template is an expression once the placeholders are filled.
+        let ident = Ident::new("value", input.span());
+        let check = expand(
+            tokens.clone(),
+            &Placeholders {
+                value: &ident.to_token_stream(),
+                method: &ident,
+                arg: Some(&ident),
+                ty: quote! { () },
+            },
+        );
+        if let Err(err) = syn::parse2::<syn::Expr>(check) {
+            return Err(syn::Error::new(
+                err.span(),
+                format!("Invalid template: {}. Use `$` for the mapped value.", err),
+            ));
+        }
+
+        Ok(Self { tokens })
+    }
+}
+
+fn is_clause_start(input: ParseStream) -> bool {
+    input.peek(Token![where])
+        || input.peek(keyword::map_args)
+        || input.peek(keyword::map_return)
+        || input.peek(keyword::with)
+}
+
+pub(crate) mod keyword {
+    syn::custom_keyword!(map_args);
+    syn::custom_keyword!(map_return);
+    syn::custom_keyword!(with);
+}
+
+/// Argument or method a hook applies to: `name`, or `method(arg, ...)` for arguments of one
+/// method.
+#[derive(Clone)]
+pub enum Selector {
+    Name(Ident),
+    Method { method: Ident, args: Vec<Ident> },
+}
+
+impl Selector {
+    fn is_name(&self, ident: &Ident) -> bool {
+        matches!(self, Self::Name(name) if name == ident)
+    }
+}
+
+impl Parse for Selector {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse::<Ident>()?;
+        if !input.peek(syn::token::Paren) {
+            return Ok(Self::Name(name));
+        }
+        let content;
+        parenthesized!(content in input);
+        Ok(Self::Method {
+            method: name,
+            args: Punctuated::<Ident, Token![,]>::parse_separated_nonempty(&content)?
+                .into_iter()
+                .collect(),
+        })
+    }
+}
+
+/// `map_args(selectors) = template` or `map_return(methods) = template`. Without selector, the
+/// hook applies to every argument or method.
+#[derive(Clone)]
+pub struct Hook {
+    pub selector: Vec<Selector>,
+    pub template: Template,
+}
+
+impl Hook {
+    /// Template of the hook selecting `arg` of `method`, or else `arg` in every method, or else
+    /// of the first one without selector.
+    pub fn find_arg<'a>(hooks: &'a [Hook], method: &Ident, arg: &Ident) -> Option<&'a Template> {
+        Self::find_by(hooks, |selector| match selector {
+            Selector::Method {
+                method: other,
+                args,
+            } => other == method && args.contains(arg),
+            Selector::Name(_) => false,
+        })
+        .or_else(|| Self::find_by(hooks, |selector| selector.is_name(arg)))
+        .or_else(|| Self::unselected(hooks))
+    }
+
+    /// Template of the hook selecting `method`, or else of the first one without selector.
+    pub fn find_method<'a>(hooks: &'a [Hook], method: &Ident) -> Option<&'a Template> {
+        Self::find_by(hooks, |selector| selector.is_name(method))
+            .or_else(|| Self::unselected(hooks))
+    }
+
+    fn find_by(hooks: &[Hook], selects: impl Fn(&Selector) -> bool) -> Option<&Template> {
+        hooks
+            .iter()
+            .find(|hook| hook.selector.iter().any(&selects))
+            .map(|hook| &hook.template)
+    }
+
+    fn unselected(hooks: &[Hook]) -> Option<&Template> {
+        hooks
+            .iter()
+            .find(|hook| hook.selector.is_empty())
+            .map(|hook| &hook.template)
+    }
+}
+
+impl Parse for Hook {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let selector = if input.peek(syn::token::Paren) {
+            let content;
+            parenthesized!(content in input);
+            Punctuated::<Selector, Token![,]>::parse_separated_nonempty(&content)?
+                .into_iter()
+                .collect()
+        } else {
+            Vec::new()
+        };
+        input.parse::<Token![=]>()?;
+        Ok(Self {
+            selector,
+            template: input.parse()?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use syn::parse_quote;
+
+    use super::*;
+
+    fn hooks(hooks: TokenStream) -> Vec<Hook> {
+        struct Hooks(Vec<Hook>);
+        impl Parse for Hooks {
+            fn parse(input: ParseStream) -> syn::Result<Self> {
+                let mut hooks = Vec::new();
+                while !input.is_empty() {
+                    input.parse::<keyword::map_args>()?;
+                    hooks.push(input.parse()?);
+                }
+                Ok(Self(hooks))
+            }
+        }
+        syn::parse2::<Hooks>(hooks).unwrap().0
+    }
+
+    fn found(template: Option<&Template>) -> Option<String> {
+        template.map(|template| template.tokens.to_string())
+    }
+
+    #[test]
+    fn arg_selectors() {
+        let hooks = hooks(quote! {
+            map_args = $.all()
+            map_args(key) = $.any()
+            map_args(get(key), set(value)) = $.get()
+        });
+        let find = |method: Ident, arg: Ident| found(Hook::find_arg(&hooks, &method, &arg));
+        assert_eq!(
+            find(parse_quote!(get), parse_quote!(key)).as_deref(),
+            Some("$ . get ()")
+        );
+        assert_eq!(
+            find(parse_quote!(set), parse_quote!(key)).as_deref(),
+            Some("$ . any ()")
+        );
+        assert_eq!(
+            find(parse_quote!(set), parse_quote!(value)).as_deref(),
+            Some("$ . get ()")
+        );
+        assert_eq!(
+            find(parse_quote!(get), parse_quote!(value)).as_deref(),
+            Some("$ . all ()")
+        );
+    }
+
+    #[test]
+    fn method_selectors() {
+        let hooks = hooks(quote! {
+            map_args(get) = $.get()
+        });
+        let find = |method: Ident| found(Hook::find_method(&hooks, &method));
+        assert_eq!(find(parse_quote!(get)).as_deref(), Some("$ . get ()"));
+        assert_eq!(find(parse_quote!(set)), None);
+    }
+
+    #[test]
+    fn placeholders() {
+        let template: Template = syn::parse2(quote! {
+            Wrapped::<$ty>::new($arg, $method, $)
+        })
+        .unwrap();
+        let expanded = template.expand(&Placeholders {
+            value: &quote! { key },
+            method: &parse_quote!(get),
+            arg: Some(&parse_quote!(key)),
+            ty: quote! { &str },
+        });
+        assert_eq!(
+            expanded.to_string(),
+            quote! { Wrapped::<&str>::new("key", "get", (key)) }.to_string()
+        );
+        assert!(template.uses("arg"));
+        assert!(template.uses("ty"));
+        assert!(!template.uses("other"));
+    }
+
+    #[test]
+    fn invalid_template() {
+        let err = syn::parse2::<Template>(quote! { $. }).err().unwrap();
+        assert!(err.to_string().starts_with("Invalid template: "));
+    }
+}
@@ -0,0 +1,354 @@
+//! TOML documents read with the `toml` crate, for INPUT.toml and the lookups in Cargo.toml.
+//! Entries keep the line they are defined on, and tables the order of their entries in the file,
+//! for error reporting.
+
+use std::fmt;
+use std::ops::Range;
+
+use toml::de::{DeTable, DeValue};
+use toml::Spanned;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    String(String),
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    /// Offset date-times, local date-times, dates and times, as written.
+    Datetime(String),
+    Array(Vec<Value>),
+    Table(Table),
+}
+
+impl Value {
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Self::String(_) => "string",
+            Self::Integer(_) => "integer",
+            Self::Float(_) => "float",
+            Self::Boolean(_) => "boolean",
+            Self::Datetime(_) => "datetime",
+            Self::Array(_) => "array",
+            Self::Table(_) => "table",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub key: String,
+    pub line: usize,
+    pub value: Value,
+    /// Line of the first character of a string value, when it is written verbatim (no escapes),
+    /// so that lines within the string map back to the file.
+    pub content_line: Option<usize>,
+}
+
+impl Entry {
+    /// Line in the file of `line` (1-based) within the string value, or the line of the entry.
+    pub fn line_in_value(&self, line: usize) -> usize {
+        match self.content_line {
+            Some(content_line) if line > 0 => content_line + line - 1,
+            _ => self.line,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Table {
+    pub line: usize,
+    pub entries: Vec<Entry>,
+}
+
+impl Table {
+    pub fn get(&self, key: &str) -> Option<&Entry> {
+        self.entries.iter().find(|entry| entry.key == key)
+    }
+}
+
+/// `line` is 0 when the error has no location.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TomlError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for TomlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+pub fn parse(text: &str) -> Result<Table, TomlError> {
+    let lines = Lines::new(text);
+    let root = DeTable::parse(text).map_err(|err| TomlError {
+        line: err.span().map_or(0, |span| lines.line(span.start)),
+        message: sentence(err.message()),
+    })?;
+    let line = lines.line(root.span().start);
+    lines.table(root.get_ref(), line)
+}
+
+/// Formats the messages of the `toml` crate, e.g. `duplicate key`, like ours.
+fn sentence(message: &str) -> String {
+    let message = message.trim_end().trim_end_matches('.');
+    let mut chars = message.chars();
+    match chars.next() {
+        Some(first) => format!("{}{}.", first.to_uppercase(), chars.as_str()),
+        None => "Invalid TOML.".to_string(),
+    }
+}
+
+/// Maps byte offsets to lines.
+struct Lines<'a> {
+    text: &'a str,
+    /// Offsets of the first byte of each line.
+    starts: Vec<usize>,
+}
+
+impl<'a> Lines<'a> {
+    fn new(text: &'a str) -> Self {
+        let starts = std::iter::once(0)
+            .chain(text.match_indices('\n').map(|(index, _)| index + 1))
+            .collect();
+        Self { text, starts }
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.starts.partition_point(|&start| start <= offset)
+    }
+
+    fn table(&self, table: &DeTable, line: usize) -> Result<Table, TomlError> {
+        let mut entries = table.iter().collect::<Vec<_>>();
+        entries.sort_by_key(|(key, _)| key.span().start);
+        let entries = entries
+            .into_iter()
+            .map(|(key, value)| {
+                Ok(Entry {
+                    key: key.get_ref().to_string(),
+                    line: self.line(key.span().start),
+                    value: self.value(value)?,
+                    content_line: self.content_line(value),
+                })
+            })
+            .collect::<Result<_, TomlError>>()?;
+        Ok(Table { line, entries })
+    }
+
+    fn value(&self, value: &Spanned<DeValue>) -> Result<Value, TomlError> {
+        let line = self.line(value.span().start);
+        let error = |message: String| TomlError { line, message };
+        Ok(match value.get_ref() {
+            DeValue::String(s) => Value::String(s.to_string()),
+            DeValue::Integer(integer) => Value::Integer(
+                i64::from_str_radix(integer.as_str(), integer.radix())
+                    .map_err(|_| error(format!("Integer `{}` out of range.", integer)))?,
+            ),
+            DeValue::Float(float) => Value::Float(
+                float
+                    .as_str()
+                    .parse()
+                    .map_err(|_| error(format!("Invalid float `{}`.", float)))?,
+            ),
+            DeValue::Boolean(boolean) => Value::Boolean(*boolean),
+            DeValue::Datetime(datetime) => Value::Datetime(datetime.to_string()),
+            DeValue::Array(values) => Value::Array(
+                values
+                    .iter()
+                    .map(|value| self.value(value))
+                    .collect::<Result<_, _>>()?,
+            ),
+            DeValue::Table(table) => Value::Table(self.table(table, line)?),
+        })
+    }
+
+    /// See [`Entry::content_line`].
+    fn content_line(&self, value: &Spanned<DeValue>) -> Option<usize> {
+        let DeValue::String(s) = value.get_ref() else {
+            return None;
+        };
+        let Range { start, end } = value.span();
+        let raw = self.text.get(start..end)?;
+        let (quote, multiline) = ["\"\"\"", "'''", "\"", "'"]
+            .into_iter()
+            .find(|quote| raw.starts_with(quote))
+            .map(|quote| (quote, quote.len() == 3))?;
+        let mut offset = start + quote.len();
+        let mut content = raw.get(quote.len()..raw.len().checked_sub(quote.len())?)?;
+        // A newline right after the opening delimiter is not part of the string.
+        if multiline {
+            for newline in ["\r\n", "\n"] {
+                if let Some(rest) = content.strip_prefix(newline) {
+                    offset += newline.len();
+                    content = rest;
+                    break;
+                }
+            }
+        }
+        (content == s.as_ref()).then(|| self.line(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(table: &Table, key: &str) -> Entry {
+        table.get(key).cloned().unwrap()
+    }
+
+    fn table(value: &Value) -> &Table {
+        match value {
+            Value::Table(table) => table,
+            value => panic!("expected a table, got {}", value.type_name()),
+        }
+    }
+
+    #[test]
+    fn scalars() {
+        let root = parse(
+            "s = 'a'\n\
+             i = 0x1f\n\
+             f = 1_000.5\n\
+             b = true\n\
+             d = 1979-05-27\n",
+        )
+        .unwrap();
+        assert_eq!(entry(&root, "s").value, Value::String("a".to_string()));
+        assert_eq!(entry(&root, "i").value, Value::Integer(31));
+        assert_eq!(entry(&root, "f").value, Value::Float(1000.5));
+        assert_eq!(entry(&root, "b").value, Value::Boolean(true));
+        assert_eq!(
+            entry(&root, "d").value,
+            Value::Datetime("1979-05-27".to_string())
+        );
+        assert_eq!(entry(&root, "d").line, 5);
+    }
+
+    #[test]
+    fn strings() {
+        let root = parse(concat!(
+            "basic = \"a\\tb\"\n",
+            "literal = 'a\\tb'\n",
+            "multi = \"\"\"\nline\n\"\"\"\n",
+            "multi_literal = '''\r\nline\r\n'''\n",
+            "trimmed = \"\"\"\\\n    line\"\"\"\n",
+        ))
+        .unwrap();
+        let string = |key| match entry(&root, key).value {
+            Value::String(s) => s,
+            value => panic!("expected a string, got {}", value.type_name()),
+        };
+        assert_eq!(string("basic"), "a\tb");
+        assert_eq!(string("literal"), "a\\tb");
+        assert_eq!(string("multi"), "line\n");
+        assert_eq!(string("multi_literal"), "line\r\n");
+        assert_eq!(string("trimmed"), "line");
+    }
+
+    #[test]
+    fn content_line() {
+        let root = parse(concat!(
+            "a = 'x'\n",
+            "b = \"\"\"\n",
+            "one\n",
+            "two\"\"\"\n",
+            "c = '''\r\n",
+            "one\r\n",
+            "'''\n",
+            "d = \"\\u0041\"\n",
+        ))
+        .unwrap();
+        assert_eq!(entry(&root, "a").content_line, Some(1));
+        assert_eq!(entry(&root, "b").content_line, Some(3));
+        assert_eq!(entry(&root, "b").line_in_value(2), 4);
+        assert_eq!(entry(&root, "c").content_line, Some(6));
+        assert_eq!(entry(&root, "d").content_line, None);
+        assert_eq!(entry(&root, "d").line_in_value(1), 8);
+    }
+
+    #[test]
+    fn tables() {
+        let root = parse(
+            "z = 1\n\
+             a.b = 2\n\
+             [table]\n\
+             key = { inline = 3, dotted.key = 4 }\n\
+             [table.sub]\n\
+             [[array]]\n\
+             x = 5\n\
+             [[array]]\n\
+             x = 6\n",
+        )
+        .unwrap();
+        // Entries keep the order of the file.
+        let keys = root
+            .entries
+            .iter()
+            .map(|entry| entry.key.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(keys, ["z", "a", "table", "array"]);
+
+        let nested = table(&entry(&root, "a").value).clone();
+        assert_eq!(entry(&nested, "b").value, Value::Integer(2));
+
+        let section = table(&entry(&root, "table").value).clone();
+        assert_eq!(section.line, 3);
+        let inline = table(&entry(&section, "key").value).clone();
+        assert_eq!(entry(&inline, "inline").value, Value::Integer(3));
+        let dotted = table(&entry(&inline, "dotted").value).clone();
+        assert_eq!(entry(&dotted, "key").value, Value::Integer(4));
+        assert!(matches!(entry(&section, "sub").value, Value::Table(_)));
+
+        let Value::Array(array) = entry(&root, "array").value else {
+            panic!("expected an array");
+        };
+        let lines = array
+            .iter()
+            .map(|value| table(value).line)
+            .collect::<Vec<_>>();
+        assert_eq!(lines, [6, 8]);
+        assert_eq!(entry(table(&array[1]), "x").line, 9);
+    }
+
+    #[test]
+    fn arrays() {
+        let root = parse("a = [1, 'two', [3], { four = 4 }, ]").unwrap();
+        let Value::Array(values) = entry(&root, "a").value else {
+            panic!("expected an array");
+        };
+        let types = values.iter().map(Value::type_name).collect::<Vec<_>>();
+        assert_eq!(types, ["integer", "string", "array", "table"]);
+    }
+
+    #[test]
+    fn duplicate_key() {
+        let err = parse("a = 1\na = 2\n").unwrap_err();
+        assert_eq!(
+            err,
+            TomlError {
+                line: 2,
+                message: "Duplicate key.".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn duplicate_table() {
+        let err = parse("[a]\nx = 1\n\n[a]\ny = 2\n").unwrap_err();
+        assert_eq!(err.line, 4);
+    }
+
+    #[test]
+    fn invalid_syntax() {
+        let err = parse("a = 1\nb = \n").unwrap_err();
+        assert_eq!(err.line, 2);
+        assert!(!err.message.is_empty());
+    }
+
+    #[test]
+    fn integer_out_of_range() {
+        let err = parse("a = 0xffffffffffffffff").unwrap_err();
+        assert_eq!(err.line, 1);
+    }
+}
@@ -1,17 +1,32 @@
-use proc_macro2::{Span, TokenStream};
-use quote::{quote, ToTokens};
-use syn::{GenericParam, Ident, Path, TraitItem, TraitItemFn};
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::{format_ident, quote, ToTokens};
+use syn::parse::ParseStream;
+use syn::punctuated::Punctuated;
+use syn::{
+    parse2, parse_quote, Attribute, Expr, ExprParen, ExprReference, FnArg, GenericArgument,
+    GenericParam, Ident, LitBool, LitStr, Meta, Pat, PatType, Path, PathArguments, ReturnType,
+    Signature, Token, TraitItem, TraitItemFn, Type,
+};
 
 use crate::generics::generic_param_name;
-use crate::input::DelegateInput;
-use crate::modifier::{GenericsRenamer, PathRootRenamer, TokenModifier};
+use crate::input::{DelegateInput, MacroInput};
+use crate::modifier::{GenericsRenamer, TokenModifier};
+use crate::target::ReceiverKind;
+use crate::template::{Hook, Placeholders};
 use crate::trait_path::ItemTraitPath;
 use crate::{Context, TraitConfig};
 
-pub fn generate_traits_match(input: &DelegateInput) -> TokenStream {
+pub fn generate_traits_match(input: &DelegateInput, macro_input: &MacroInput) -> TokenStream {
     let mut res = TokenStream::default();
-    let root = input.root();
-    for trait_input in &input.traits {
+    // Without a facade, callers depend on delegate-trait, possibly under another name.
+    let root = if input.is_single_crate() {
+        quote! { ::delegate_trait::dependency_path("delegate_trait")? }
+    } else {
+        let root = input.root();
+        quote! { ::syn::parse2::<::syn::Path>(::quote::quote! { #root }).unwrap() }
+    };
+    for entry in &macro_input.traits {
+        let trait_input = &entry.item;
         let trait_ident_string = &trait_input
             .path
             .segments
@@ -19,12 +34,29 @@ pub fn generate_traits_match(input: &DelegateInput) -> TokenStream {
             .expect("generate_traits_match: expected ident")
             .ident
             .to_string();
+        let defaults = &entry.defaults;
+        let config = if defaults.is_empty() {
+            quote! { config }
+        } else {
+            quote! {
+                &config.with_defaults(&::syn::parse2::<::delegate_trait::TraitDefaults>(::quote::quote! { #defaults }).unwrap())
+            }
+        };
+        let self_crate = match &entry.self_crate {
+            Some(self_crate) => {
+                let self_crate = self_crate.to_string();
+                quote! { Some(#self_crate) }
+            }
+            None => quote! { None },
+        };
         let trait_impl = quote! {
-            let trait_input = ::syn::parse2::<::delegate_trait::ItemTraitPath>(::quote::quote! { #trait_input }).unwrap();
-            let root = ::syn::parse2::<::syn::Path>(::quote::quote! { #root }).unwrap();
-            ::delegate_trait::generate_trait_impl(&context, config, root, trait_input)?
+            let mut trait_input = ::syn::parse2::<::delegate_trait::ItemTraitPath>(::quote::quote! { #trait_input }).unwrap();
+            ::delegate_trait::resolve_self_crate(&mut trait_input, #self_crate, config)?;
+            let root = #root;
+            generate(#config, root, trait_input)?
         };
-        res.extend(quote! { #trait_ident_string => { #trait_impl }, });
+        let cfg_attrs = trait_input.cfg_attrs();
+        res.extend(quote! { #(#cfg_attrs)* #trait_ident_string => { #trait_impl }, });
     }
     res
 }
@@ -35,15 +67,158 @@ pub fn generate_trait_impl(
     root: Path,
     mut trait_input: ItemTraitPath,
 ) -> syn::Result<TokenStream> {
-    if let Ok(package_name) = std::env::var("CARGO_PKG_NAME") {
-        let mut renamer = PathRootRenamer {
-            original: package_name.replace("-", "_"),
-            rename: Ident::new("crate", Span::call_site()),
-            remove_leading_colon: true,
+    trait_input.resolve_uses()?;
+
+    let mut generics_renamer = generics_renamer(&trait_input, config)?;
+
+    for target in [&config.to, &config.to_ref, &config.to_mut, &config.to_owned]
+        .into_iter()
+        .flatten()
+    {
+        context.check_target(target)?;
+    }
+
+    let trait_path = &config.path;
+    match (&trait_input.unsafety, &config.unsafety) {
+        (Some(_), None) => {
+            return Err(syn::Error::new_spanned(
+                trait_path,
+                format!(
+                    "Trait {} is unsafe, write `unsafe {}` to implement it.",
+                    trait_input.path.segments.last().unwrap().ident,
+                    trait_path.to_token_stream()
+                ),
+            ))
+        }
+        (None, Some(unsafety)) => {
+            return Err(syn::Error::new_spanned(
+                unsafety,
+                format!(
+                    "Trait {} is not unsafe.",
+                    trait_input.path.segments.last().unwrap().ident
+                ),
+            ))
+        }
+        _ => (),
+    }
+
+    let through_trait = if config.generics.params.is_empty() {
+        quote! { #trait_path }
+    } else {
+        let generics = &config.generics;
+        quote! { #trait_path :: #generics }
+    };
+
+    // Delegated methods, grouped by target.
+    let mut methods = Vec::<(String, TokenStream, TokenStream)>::new();
+    let mut forwarded = TokenStream::default();
+
+    let trait_input_items = &trait_input.items;
+
+    for method in trait_input_items
+        .iter()
+        .filter_map(|item| trait_item_as_fn(item))
+    {
+        let mut method = method.clone();
+
+        method.attrs = method.attrs.iter().filter_map(forwarded_attr).collect();
+        method.default = None;
+        method.semi_token = Some(Default::default());
+        method.sig.ident.set_span(Span::call_site());
+
+        generics_renamer.modify_trait_item_fn(&mut method);
+
+        let takes_self = method.sig.inputs.iter().any(|input| match input {
+            FnArg::Typed(input) => mentions_self(&input.ty),
+            FnArg::Receiver(_) => false,
+        });
+        let maps_args = method
+            .sig
+            .inputs
+            .iter()
+            .enumerate()
+            .any(|(index, input)| match input {
+                FnArg::Typed(input) => Hook::find_arg(
+                    &config.map_args,
+                    &method.sig.ident,
+                    &arg_ident(input, index),
+                )
+                .is_some(),
+                FnArg::Receiver(_) => false,
+            });
+        if method.sig.receiver().is_none()
+            || takes_self
+            || maps_args
+            || config.around.is_some()
+            || config.bind.is_some()
+        {
+            forwarded.extend(forward_method(config, &through_trait, method)?);
+            continue;
+        }
+
+        let template = map_return(config, &method.sig, quote! { $ })?;
+        let expr = (template.to_string() != "$").then(|| quote! { #[expr(#template)] });
+
+        let target = config.method_target(&method.sig)?;
+        let method = quote! {
+            #[through(#through_trait)]
+            #expr
+            #method
         };
-        renamer.modify_item_trait_path(&mut trait_input);
+        let key = target.to_string();
+        match methods.iter_mut().find(|(other, _, _)| *other == key) {
+            Some((_, _, methods)) => methods.extend(method),
+            None => methods.push((key, target, method)),
+        }
+    }
+
+    let wi = config.wi.clone().unwrap_or_default();
+    let mut targets = TokenStream::default();
+    for (_, target, methods) in methods {
+        targets.extend(quote! {
+            to #target {
+                #methods
+            }
+        });
     }
+    let delegated = if targets.is_empty() {
+        TokenStream::default()
+    } else {
+        quote! {
+            #root::delegate! {
+                #targets
+            }
+        }
+    };
+
+    let methods = ::quote::quote! {
+        #wi
+
+        #forwarded
+
+        #delegated
+    };
+
+    let trait_impl = config.wrap_methods(
+        context,
+        &trait_path.to_token_stream(),
+        &config.generics,
+        &methods,
+    );
+    let cfg_attrs = trait_input.cfg_attrs();
+
+    Ok(quote! {
+        #(#cfg_attrs)*
+        #trait_impl
+    })
+}
 
+/// Renames the generic parameters of the trait definition to the ones given in `config`, e.g.
+/// `K` to `Key` for `Store<Key>`.
+pub(crate) fn generics_renamer(
+    trait_input: &ItemTraitPath,
+    config: &TraitConfig,
+) -> syn::Result<GenericsRenamer> {
     let mut generics_renamer = GenericsRenamer::default();
 
     for couple in trait_input
@@ -66,66 +241,447 @@ pub fn generate_trait_impl(
             }
             _ => {
                 return Err(syn::Error::new_spanned(
-                    &couple.1,
-                    &format!(
+                    couple.1,
+                    format!(
                         "Expected {}, got {}.",
-                        generic_param_name(&couple.0),
-                        generic_param_name(&couple.1)
+                        generic_param_name(couple.0),
+                        generic_param_name(couple.1)
                     ),
                 ))
             }
         }
     }
 
-    let trait_path = &config.path;
-    let through_trait = if config.generics.params.is_empty() {
-        quote! { #trait_path }
+    Ok(generics_renamer)
+}
+
+/// Forwards a method without going through delegate!: associated functions, which it cannot
+/// call without a receiver, are forwarded to `<inner as Trait>::f(...)`, and methods taking
+/// `Self` arguments to `Trait::f(to, ...)` with these arguments projected like `to`. With
+/// `around`, every method is forwarded here so that the call can be passed to the hook, and with
+/// `to fn`, so that the target is bound before the call.
+fn forward_method(
+    config: &TraitConfig,
+    through_trait: &TokenStream,
+    method: TraitItemFn,
+) -> syn::Result<TokenStream> {
+    let TraitItemFn {
+        mut attrs, mut sig, ..
+    } = method;
+    let name = sig.ident.clone();
+
+    let bound = config.bound_target(&sig)?;
+    // Names the arguments are bound to before `around`, with the argument itself for the others.
+    let mut names = Vec::new();
+    let mut args = Vec::new();
+    if sig.receiver().is_some() {
+        names.push(Ident::new("__delegate_self", Span::call_site()));
+        args.push(config.method_target(&sig)?);
+    }
+    for (index, input) in sig.inputs.iter_mut().enumerate() {
+        let FnArg::Typed(input) = input else {
+            continue;
+        };
+        let arg = arg_ident(input, index);
+        *input.pat = parse_quote! { #arg };
+        names.push(arg.clone());
+        if mentions_self(&input.ty) {
+            args.push(project_self_arg(config, &name, &arg, &input.ty)?);
+        } else if let Some(template) = Hook::find_arg(&config.map_args, &name, &arg) {
+            args.push(template.expand(&Placeholders {
+                value: &arg.to_token_stream(),
+                method: &name,
+                arg: Some(&arg),
+                ty: input.ty.to_token_stream(),
+            }));
+        } else {
+            args.push(arg.to_token_stream());
+        }
+    }
+
+    let turbofish = sig
+        .generics
+        .params
+        .iter()
+        .filter_map(|param| match param {
+            GenericParam::Type(param) => Some(&param.ident),
+            GenericParam::Const(param) => Some(&param.ident),
+            GenericParam::Lifetime(_) => None,
+        })
+        .collect::<Vec<_>>();
+    let turbofish = (!turbofish.is_empty()).then(|| quote! { ::<#(#turbofish),*> });
+
+    let function = if sig.receiver().is_some() {
+        quote! { #through_trait::#name }
     } else {
-        let generics = &config.generics;
-        quote! { #trait_path :: #generics }
+        let inner = config.inner.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(
+                &config.path,
+                format!(
+                    "Function {} has no receiver, expected `inner = <type>` to forward it to.",
+                    name
+                ),
+            )
+        })?;
+        quote! { <#inner as #through_trait>::#name }
+    };
+
+    // With `around`, the target and the arguments are evaluated before the hook, so that `?` in
+    // them returns from the method rather than from the closure.
+    let mut hoisted = TokenStream::new();
+    if config.around.is_some() {
+        for (name, arg) in names.into_iter().zip(&mut args) {
+            if name != arg.to_string() {
+                hoisted.extend(quote! { let #name = #arg; });
+                *arg = name.into_token_stream();
+            }
+        }
+    }
+
+    let mut call = quote! { #function #turbofish(#(#args),*) };
+    if sig.unsafety.is_some() {
+        call = quote! { unsafe { #call } };
+    }
+    if let Some(around) = &config.around {
+        let trait_name = &config
+            .path
+            .segments
+            .last()
+            .expect("forward_method: trait path expected")
+            .ident;
+        let trait_name = LitStr::new(&trait_name.to_string(), trait_name.span());
+        let method_name = LitStr::new(&name.to_string(), name.span());
+        call = quote! { #around(#trait_name, #method_name, || #call) };
+    }
+    let call = apply_delegate_attrs(&name, &mut attrs, sig.asyncness.is_some(), call)?;
+    let call = map_return(config, &sig, call)?;
+
+    Ok(quote! {
+        #(#attrs)*
+        #[inline]
+        #sig {
+            #bound
+            #hoisted
+            #call
+        }
+    })
+}
+
+/// Name of the argument at `index` in the forwarding method, generated for patterns.
+fn arg_ident(input: &PatType, index: usize) -> Ident {
+    match &*input.pat {
+        Pat::Ident(pat) if pat.subpat.is_none() => pat.ident.clone(),
+        _ => format_ident!("arg{}", index),
+    }
+}
+
+/// Projects the argument `arg` of type `Self`, `&Self` or `&mut Self` the way `to` projects
+/// `self`, e.g. `&other.inner` for `other: &Self` with `to &self.inner`. With `via`, the
+/// argument goes through the adapter, and a target specific to its kind (e.g. `ref = ...` for
+/// `&Self`) is used as is.
+fn project_self_arg(
+    config: &TraitConfig,
+    name: &Ident,
+    arg: &Ident,
+    ty: &Type,
+) -> syn::Result<TokenStream> {
+    let (kind, ty) = match ty {
+        Type::Reference(reference) if reference.mutability.is_some() => {
+            (ReceiverKind::Mut, &*reference.elem)
+        }
+        Type::Reference(reference) => (ReceiverKind::Ref, &*reference.elem),
+        ty => (ReceiverKind::Owned, ty),
     };
+    if !is_self(ty) {
+        return Err(syn::Error::new_spanned(
+            &config.path,
+            format!(
+                "Method {} takes an argument of type `{}`, only `Self`, `&Self` and `&mut Self` can be projected.",
+                name,
+                ty.to_token_stream()
+            ),
+        ));
+    }
 
-    let mut methods = TokenStream::default();
+    if let Some(target) = config.receiver_target(kind) {
+        return Ok(replace_self(target.to_token_stream(), arg));
+    }
 
-    let trait_input_items = &trait_input.items;
+    let mut to = config.to().map_err(|_| {
+        syn::Error::new_spanned(
+            &config.path,
+            format!(
+                "Method {} takes `Self` as argument, expected `to <expr>` to project it.",
+                name
+            ),
+        )
+    })?;
 
-    for method in trait_input_items
-        .iter()
-        .filter_map(|item| trait_item_as_fn(item))
+    if let Some(via) = &config.via {
+        let to = parse2::<Expr>(replace_self(to.to_token_stream(), arg))?;
+        return via.access(&to, kind);
+    }
+
+    while let Expr::Reference(ExprReference { expr, .. }) | Expr::Paren(ExprParen { expr, .. }) = to
     {
-        let mut method = method.clone();
+        to = expr;
+    }
+    let projected = replace_self(to.to_token_stream(), arg);
 
-        method.default = None;
-        method.semi_token = Some(Default::default());
-        method.sig.ident.set_span(Span::call_site());
+    Ok(match kind {
+        ReceiverKind::Ref => quote! { &(#projected) },
+        ReceiverKind::Mut => quote! { &mut (#projected) },
+        ReceiverKind::Owned => projected,
+    })
+}
 
-        generics_renamer.modify_trait_item_fn(&mut method);
+fn replace_self(tokens: TokenStream, arg: &Ident) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Ident(ident) if ident == "self" => TokenTree::Ident(arg.clone()),
+            TokenTree::Group(group) => {
+                let mut replaced = Group::new(group.delimiter(), replace_self(group.stream(), arg));
+                replaced.set_span(group.span());
+                TokenTree::Group(replaced)
+            }
+            token => token,
+        })
+        .collect()
+}
 
-        methods.extend(quote! {
-            #[through(#through_trait)]
-            #method
+/// Applies the `map_return` hook of the method to `call`, then builds `Self` back.
+fn map_return(
+    config: &TraitConfig,
+    sig: &Signature,
+    call: TokenStream,
+) -> syn::Result<TokenStream> {
+    let call = match Hook::find_method(&config.map_return, &sig.ident) {
+        Some(template) => template.expand(&Placeholders {
+            value: &call,
+            method: &sig.ident,
+            arg: None,
+            ty: match &sig.output {
+                ReturnType::Type(_, ty) => ty.to_token_stream(),
+                ReturnType::Default => quote! { () },
+            },
+        }),
+        None => call,
+    };
+    Ok(wrap_self_return(config, &sig.ident, &sig.output, &call)?.unwrap_or(call))
+}
+
+/// Converts `call`, the value returned by the inner implementation, back into `Self` with
+/// `wrap` when the method returns `Self`, `Option<Self>` or `Result<Self, E>`. Returns `None`
+/// when the return type does not involve `Self`.
+fn wrap_self_return(
+    config: &TraitConfig,
+    name: &Ident,
+    output: &ReturnType,
+    call: &TokenStream,
+) -> syn::Result<Option<TokenStream>> {
+    let ReturnType::Type(_, ty) = output else {
+        return Ok(None);
+    };
+    if !mentions_self(ty) {
+        return Ok(None);
+    }
+
+    let wrap = || {
+        config.wrap.as_ref().ok_or_else(|| {
+            syn::Error::new_spanned(
+                &config.path,
+                format!(
+                    "Method {} returns `{}`, expected `wrap = <constructor>` to build `Self` back.",
+                    name,
+                    ty.to_token_stream()
+                ),
+            )
         })
+    };
+
+    if is_self(ty) {
+        let wrap = wrap()?;
+        return Ok(Some(quote! { (#wrap)(#call) }));
     }
 
-    let to = &config.to;
-    let wi = config.wi.clone().unwrap_or_default();
+    if let Type::Path(path) = &**ty {
+        let last = path.path.segments.last().unwrap();
+        if path.qself.is_none() && (last.ident == "Option" || last.ident == "Result") {
+            if let PathArguments::AngleBracketed(arguments) = &last.arguments {
+                let mut arguments = arguments.args.iter();
+                let wrapped =
+                    matches!(arguments.next(), Some(GenericArgument::Type(ty)) if is_self(ty));
+                let others = arguments.all(|argument| !mentions_self(argument));
+                if wrapped && others {
+                    let wrap = wrap()?;
+                    return Ok(Some(quote! { (#call).map(#wrap) }));
+                }
+            }
+        }
+    }
 
-    let methods = ::quote::quote! {
-        #wi
+    Err(syn::Error::new_spanned(
+        &config.path,
+        format!(
+            "Method {} returns `{}`, only `Self`, `Option<Self>` and `Result<Self, E>` can be built back.",
+            name,
+            ty.to_token_stream()
+        ),
+    ))
+}
 
-        #root::delegate! {
-            to #to {
-                #methods
+pub(crate) fn is_self(ty: &Type) -> bool {
+    matches!(ty, Type::Path(path) if path.qself.is_none() && path.path.is_ident("Self"))
+}
+
+/// Whether `ty` refers to the implementing type, other than through an associated type.
+pub(crate) fn mentions_self(ty: &impl ToTokens) -> bool {
+    fn visit(tokens: TokenStream) -> bool {
+        let mut tokens = tokens.into_iter().peekable();
+        while let Some(token) = tokens.next() {
+            let found = match token {
+                TokenTree::Ident(ident) => {
+                    ident == "Self"
+                        && !matches!(tokens.peek(), Some(TokenTree::Punct(punct)) if punct.as_char() == ':')
+                }
+                TokenTree::Group(group) => visit(group.stream()),
+                _ => false,
+            };
+            if found {
+                return true;
             }
         }
+        false
+    }
+    visit(ty.to_token_stream())
+}
+
+/// Attributes of trait methods kept on the forwarding method: `cfg` keeps conditional methods
+/// conditional, `allow` silences the lints the trait author expected, codegen hints still apply,
+/// and the attributes of `delegate!` ([`DELEGATE_ATTRS`]) configure the forwarding. Everything
+/// else is dropped: documentation, `deprecated` or `must_use` are rejected or ignored on impl
+/// items, and other lint levels could fire on a body the trait author did not write.
+const FORWARDED_ATTRS: &[&str] = &["cfg", "allow", "inline", "cold", "track_caller"];
+
+/// Attributes read by `delegate!`, which are not attributes of plain impl methods.
+const DELEGATE_ATTRS: &[&str] = &[
+    "call", "expr", "field", "into", "try_into", "unwrap", "await", "through",
+];
+
+fn is_forwarded(path: &Path) -> bool {
+    FORWARDED_ATTRS
+        .iter()
+        .chain(DELEGATE_ATTRS)
+        .any(|name| path.is_ident(name))
+}
+
+fn is_delegate_attr(path: &Path) -> bool {
+    DELEGATE_ATTRS.iter().any(|name| path.is_ident(name))
+}
+
+/// The attribute as forwarded, if it is. `cfg_attr` keeps the forwarded attributes it applies.
+fn forwarded_attr(attr: &Attribute) -> Option<Attribute> {
+    if is_forwarded(attr.path()) {
+        return Some(attr.clone());
+    }
+    let (predicate, attrs) = cfg_attr(attr)?;
+    let attrs = attrs
+        .into_iter()
+        .filter(|meta| is_forwarded(meta.path()))
+        .collect::<Vec<_>>();
+    if attrs.is_empty() {
+        return None;
+    }
+    Some(parse_quote! { #[cfg_attr(#predicate, #(#attrs),*)] })
+}
+
+/// Predicate and attributes of `#[cfg_attr(predicate, attrs...)]`.
+fn cfg_attr(attr: &Attribute) -> Option<(Meta, Punctuated<Meta, Token![,]>)> {
+    if !attr.path().is_ident("cfg_attr") {
+        return None;
+    }
+    attr.parse_args_with(|input: ParseStream| {
+        let predicate = input.parse::<Meta>()?;
+        input.parse::<Token![,]>()?;
+        let attrs = Punctuated::<Meta, Token![,]>::parse_terminated(input)?;
+        Ok((predicate, attrs))
+    })
+    .ok()
+}
+
+/// Applies the `delegate!` attributes of a method forwarded without it to `call`, the value
+/// returned by the target, and removes them from `attrs`: `#[into]`, `#[try_into]` and
+/// `#[unwrap]` convert the value the way `delegate!` does and `#[await(false)]` leaves it a
+/// future. The attributes changing what is called cannot apply.
+fn apply_delegate_attrs(
+    name: &Ident,
+    attrs: &mut Vec<Attribute>,
+    asyncness: bool,
+    call: TokenStream,
+) -> syn::Result<TokenStream> {
+    let unsupported = |path: &Path| {
+        syn::Error::new_spanned(
+            path,
+            format!(
+                "Method {} uses `#[{}]`, which only applies to methods delegated through `delegate!`, not to the ones forwarded for `around`, `to fn`, `map_args`, `Self` arguments or associated functions.",
+                name,
+                path.to_token_stream()
+            ),
+        )
     };
 
-    Ok(config.wrap_methods(
-        &context,
-        &trait_path.to_token_stream(),
-        &config.generics,
-        &methods,
-    ))
+    enum Conversion {
+        /// `#[into(Type)]` or `#[into]`.
+        Into(Option<TokenStream>),
+        TryInto,
+        Unwrap,
+    }
+
+    let mut awaits = asyncness;
+    let mut conversions = Vec::new();
+    let mut kept = Vec::new();
+    for attr in attrs.drain(..) {
+        if let Some((_, metas)) = cfg_attr(&attr) {
+            if let Some(meta) = metas.iter().find(|meta| is_delegate_attr(meta.path())) {
+                return Err(unsupported(meta.path()));
+            }
+        }
+        if !is_delegate_attr(attr.path()) {
+            kept.push(attr);
+            continue;
+        }
+        let path = attr.path();
+        if path.is_ident("into") {
+            conversions.push(Conversion::Into(match &attr.meta {
+                Meta::List(list) => Some(list.parse_args::<Type>()?.into_token_stream()),
+                _ => None,
+            }));
+        } else if path.is_ident("try_into") {
+            conversions.push(Conversion::TryInto);
+        } else if path.is_ident("unwrap") {
+            conversions.push(Conversion::Unwrap);
+        } else if path.is_ident("await") {
+            awaits = attr.parse_args::<LitBool>()?.value;
+        } else {
+            return Err(unsupported(path));
+        }
+    }
+    *attrs = kept;
+
+    let mut call = call;
+    if awaits {
+        call = quote! { #call.await };
+    }
+    for conversion in conversions {
+        call = match conversion {
+            Conversion::Into(Some(ty)) => quote! { ::core::convert::Into::<#ty>::into(#call) },
+            Conversion::Into(None) => quote! { ::core::convert::Into::into(#call) },
+            Conversion::TryInto => quote! { ::core::convert::TryInto::try_into(#call) },
+            Conversion::Unwrap => quote! { (#call).unwrap() },
+        };
+    }
+    Ok(call)
 }
 
 fn trait_item_as_fn(trait_item: &TraitItem) -> Option<&TraitItemFn> {
@@ -134,3 +690,338 @@ fn trait_item_as_fn(trait_item: &TraitItem) -> Option<&TraitItemFn> {
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use quote::ToTokens;
+
+    use super::*;
+
+    fn forwarded(attr: Attribute) -> Option<String> {
+        forwarded_attr(&attr).map(|attr| attr.to_token_stream().to_string())
+    }
+
+    #[test]
+    fn forwarded_attrs() {
+        assert_eq!(
+            forwarded(parse_quote! { #[cfg(feature = "a")] }).as_deref(),
+            Some("# [cfg (feature = \"a\")]")
+        );
+        assert_eq!(
+            forwarded(parse_quote! { #[allow(unused)] }).as_deref(),
+            Some("# [allow (unused)]")
+        );
+        assert_eq!(
+            forwarded(parse_quote! { #[inline] }).as_deref(),
+            Some("# [inline]")
+        );
+        assert_eq!(
+            forwarded(parse_quote! { #[into] }).as_deref(),
+            Some("# [into]")
+        );
+    }
+
+    #[test]
+    fn dropped_attrs() {
+        assert_eq!(forwarded(parse_quote! { #[doc = "Gets."] }), None);
+        assert_eq!(forwarded(parse_quote! { #[deprecated] }), None);
+        assert_eq!(forwarded(parse_quote! { #[must_use] }), None);
+        assert_eq!(forwarded(parse_quote! { #[warn(missing_docs)] }), None);
+        assert_eq!(forwarded(parse_quote! { #[rustfmt::skip] }), None);
+        assert_eq!(forwarded(parse_quote! { #[unknown_tool_attr] }), None);
+    }
+
+    #[test]
+    fn cfg_attr() {
+        assert_eq!(
+            forwarded(parse_quote! { #[cfg_attr(test, allow(unused), doc = "Gets.", inline)] })
+                .as_deref(),
+            Some("# [cfg_attr (test , allow (unused) , inline)]")
+        );
+        assert_eq!(
+            forwarded(parse_quote! { #[cfg_attr(test, deprecated, must_use)] }),
+            None
+        );
+    }
+
+    #[test]
+    fn around_evaluates_arguments_first() {
+        let config: TraitConfig =
+            parse_quote! { Store to self.inner.as_ref()? around = trace map_args(key) = $.trim() };
+        let method: TraitItemFn = parse_quote! { async fn get(&self, key: &str) -> Option<u8>; };
+        let forwarded = forward_method(&config, &quote! { Store }, method).unwrap();
+        assert_eq!(
+            forwarded.to_string(),
+            quote! {
+                #[inline]
+                async fn get(&self, key: &str) -> Option<u8> {
+                    let __delegate_self = ::core::iter::IntoIterator::into_iter(self.inner.as_ref()).next()?;
+                    let key = (key).trim();
+                    trace("Store", "get", || Store::get(__delegate_self, key)).await
+                }
+            }
+            .to_string()
+        );
+    }
+
+    fn applied(attrs: Vec<Attribute>, asyncness: bool) -> syn::Result<(String, usize)> {
+        let mut attrs = attrs;
+        let call = apply_delegate_attrs(&parse_quote!(get), &mut attrs, asyncness, quote! { f() })?;
+        Ok((call.to_string(), attrs.len()))
+    }
+
+    #[test]
+    fn delegate_attrs_applied() {
+        assert_eq!(
+            applied(
+                vec![parse_quote! { #[inline] }, parse_quote! { #[into] }],
+                false
+            )
+            .unwrap(),
+            (quote! { ::core::convert::Into::into(f()) }.to_string(), 1)
+        );
+        assert_eq!(
+            applied(
+                vec![parse_quote! { #[try_into] }, parse_quote! { #[unwrap] }],
+                true
+            )
+            .unwrap(),
+            (
+                quote! { (::core::convert::TryInto::try_into(f().await)).unwrap() }.to_string(),
+                0
+            )
+        );
+        assert_eq!(
+            applied(vec![parse_quote! { #[into(u64)] }], false)
+                .unwrap()
+                .0,
+            quote! { ::core::convert::Into::<u64>::into(f()) }.to_string()
+        );
+    }
+
+    #[test]
+    fn delegate_attrs_unsupported() {
+        let message = |attr: Attribute| applied(vec![attr], false).unwrap_err().to_string();
+        assert_eq!(
+            message(parse_quote! { #[call(other)] }),
+            "Method get uses `#[call]`, which only applies to methods delegated through `delegate!`, not to the ones forwarded for `around`, `to fn`, `map_args`, `Self` arguments or associated functions."
+        );
+        assert!(message(parse_quote! { #[cfg_attr(test, into)] })
+            .starts_with("Method get uses `#[into]`"));
+    }
+
+    /// Implements `definition` for `Wrapper` with the macro arguments `config`.
+    fn generate(config: TokenStream, definition: TokenStream) -> syn::Result<String> {
+        let context = Context::for_type(parse_quote! { Wrapper }, Default::default());
+        let config = parse2::<TraitConfig>(config)?;
+        let trait_input = parse2::<ItemTraitPath>(definition)?;
+        generate_trait_impl(
+            &context,
+            &config,
+            parse_quote! { ::delegate_trait },
+            trait_input,
+        )
+        .map(|tokens| tokens.to_string())
+    }
+
+    #[test]
+    fn unsafe_trait() {
+        let definition = quote! { unsafe trait a::Raw { unsafe fn raw(&self) -> usize; } };
+        let generated = generate(quote! { unsafe Raw to &self.0 }, definition.clone()).unwrap();
+        assert!(generated.starts_with("unsafe impl Raw for Wrapper"));
+
+        let err = generate(quote! { Raw to &self.0 }, definition).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Trait Raw is unsafe, write `unsafe Raw` to implement it."
+        );
+    }
+
+    #[test]
+    fn safe_trait() {
+        let err = generate(
+            quote! { unsafe Store to &self.0 },
+            quote! { trait a::Store { fn get(&self) -> u32; } },
+        )
+        .unwrap_err();
+        assert_eq!(err.to_string(), "Trait Store is not unsafe.");
+    }
+
+    #[test]
+    fn associated_function_without_inner() {
+        let err = generate(
+            quote! { Build to &self.0 },
+            quote! { trait a::Build { fn name() -> &'static str; } },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Function name has no receiver, expected `inner = <type>` to forward it to."
+        );
+    }
+
+    #[test]
+    fn associated_function() {
+        let generated = generate(
+            quote! { Build inner = Mem },
+            quote! { trait a::Build { fn name() -> &'static str; } },
+        )
+        .unwrap();
+        assert!(generated.contains(&quote! { <Mem as Build>::name() }.to_string()));
+    }
+
+    #[test]
+    fn wrapped_returns() {
+        let generated = generate(
+            quote! { Build inner = Mem wrap = Wrapper::from },
+            quote! {
+                trait a::Build {
+                    fn new() -> Self;
+                    fn parse(key: &str) -> Option<Self>;
+                    fn load(key: &str) -> Result<Self, String>;
+                }
+            },
+        )
+        .unwrap();
+        for call in [
+            quote! { (Wrapper::from)(<Mem as Build>::new()) },
+            quote! { (<Mem as Build>::parse(key)).map(Wrapper::from) },
+            quote! { (<Mem as Build>::load(key)).map(Wrapper::from) },
+        ] {
+            assert!(
+                generated.contains(&call.to_string()),
+                "{} not in {}",
+                call,
+                generated
+            );
+        }
+    }
+
+    #[test]
+    fn wrapped_return_errors() {
+        let err = generate(
+            quote! { Build inner = Mem },
+            quote! { trait a::Build { fn new() -> Self; } },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method new returns `Self`, expected `wrap = <constructor>` to build `Self` back."
+        );
+
+        let err = generate(
+            quote! { Build inner = Mem wrap = Wrapper::from },
+            quote! { trait a::Build { fn all() -> Vec<Self>; } },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method all returns `Vec < Self >`, only `Self`, `Option<Self>` and `Result<Self, E>` can be built back."
+        );
+    }
+
+    #[test]
+    fn projected_self_args() {
+        let definition = quote! {
+            trait a::Merge {
+                fn merge(&mut self, other: &Self);
+                fn drain(&mut self, other: &mut Self);
+                fn combine(self, other: Self) -> usize;
+            }
+        };
+        let generated = generate(quote! { Merge to &self.inner }, definition.clone()).unwrap();
+        for call in [
+            quote! { Merge::merge(&self.inner, &(other.inner)) },
+            quote! { Merge::drain(&self.inner, &mut (other.inner)) },
+            quote! { Merge::combine(&self.inner, other.inner) },
+        ] {
+            assert!(
+                generated.contains(&call.to_string()),
+                "{} not in {}",
+                call,
+                generated
+            );
+        }
+
+        let generated = generate(
+            quote! { Merge ref = &self.inner mut = &mut self.inner owned = self.inner },
+            definition.clone(),
+        )
+        .unwrap();
+        for call in [
+            quote! { Merge::merge(&mut self.inner, &other.inner) },
+            quote! { Merge::drain(&mut self.inner, &mut other.inner) },
+            quote! { Merge::combine(self.inner, other.inner) },
+        ] {
+            assert!(
+                generated.contains(&call.to_string()),
+                "{} not in {}",
+                call,
+                generated
+            );
+        }
+
+        let generated = generate(quote! { Merge to self.0 via deref_mut() }, definition).unwrap();
+        for call in [
+            quote! { Merge::merge(&mut *(self.0), &*(other.0)) },
+            quote! { Merge::drain(&mut *(self.0), &mut *(other.0)) },
+            quote! { Merge::combine(*(self.0), *(other.0)) },
+        ] {
+            assert!(
+                generated.contains(&call.to_string()),
+                "{} not in {}",
+                call,
+                generated
+            );
+        }
+    }
+
+    #[test]
+    fn projected_self_arg_errors() {
+        let err = generate(
+            quote! { Merge to &self.inner },
+            quote! { trait a::Merge { fn merge_all(&mut self, others: Vec<Self>); } },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method merge_all takes an argument of type `Vec < Self >`, only `Self`, `&Self` and `&mut Self` can be projected."
+        );
+
+        let err = generate(
+            quote! { Merge ref = &self.inner },
+            quote! { trait a::Merge { fn merge(&self, other: Self); } },
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Method merge takes `Self` as argument, expected `to <expr>` to project it."
+        );
+    }
+
+    #[test]
+    fn cfg_trait() {
+        let definition = quote! {
+            #[cfg(feature = "async")]
+            trait a::Store { fn get(&self) -> u32; }
+        };
+        let input = parse2::<DelegateInput>(quote! {
+            macro_ident = delegate;
+            traits = { #definition };
+        })
+        .unwrap();
+        let arms = generate_traits_match(&input, &input.macros[0]).to_string();
+        assert!(
+            arms.starts_with("# [cfg (feature = \"async\")] \"Store\" =>"),
+            "{}",
+            arms
+        );
+
+        let generated = generate(quote! { Store to &self.0 }, definition).unwrap();
+        assert!(
+            generated.starts_with("# [cfg (feature = \"async\")] impl Store for Wrapper"),
+            "{}",
+            generated
+        );
+    }
+}
@@ -1,14 +1,20 @@
-//! Rewrite of `syn::ItemTrait` with a `Path` instead of an `Ident`.
+//! Rewrite of `syn::ItemTrait` with a `Path` instead of an `Ident`, preceded by the `use`
+//! declarations its signatures rely on.
 
 use quote::{ToTokens, TokenStreamExt};
 use syn::parse::{Parse, ParseStream};
 use syn::punctuated::Punctuated;
 use syn::{
-    braced, bracketed, parse2, token, AttrStyle, Attribute, Generics, ImplRestriction, Path,
-    PathArguments, Token, TraitItem, TypeParamBound, Visibility,
+    braced, bracketed, parse2, token, AttrStyle, Attribute, Generics, ImplRestriction, ItemUse,
+    Path, PathArguments, Token, TraitItem, TypeParamBound, Visibility,
 };
 
+use crate::modifier::{TokenModifier, UseResolver};
+
+#[derive(Clone)]
 pub struct ItemTraitPath {
+    /// Imports used to qualify the paths of the definition, see [`ItemTraitPath::resolve_uses`].
+    pub uses: Vec<ItemUse>,
     pub attrs: Vec<Attribute>,
     pub vis: Visibility,
     pub unsafety: Option<Token![unsafe]>,
@@ -23,9 +29,37 @@ pub struct ItemTraitPath {
     pub items: Vec<TraitItem>,
 }
 
+impl ItemTraitPath {
+    /// `#[cfg(...)]` attributes of the trait, carried over to its match arm and impls. The arm is
+    /// compiled with the features of the proc-macro crate, the impls with the ones of the caller.
+    pub fn cfg_attrs(&self) -> impl Iterator<Item = &Attribute> {
+        self.attrs
+            .iter()
+            .filter(|attr| matches!(attr.style, AttrStyle::Outer) && attr.path().is_ident("cfg"))
+    }
+
+    /// Rewrites the paths of the definition starting with a name imported by `uses` into the
+    /// imported path, so that they do not depend on the scope where the macro expands. `uses`
+    /// is emptied.
+    pub fn resolve_uses(&mut self) -> syn::Result<()> {
+        let uses = std::mem::take(&mut self.uses);
+        if uses.is_empty() {
+            return Ok(());
+        }
+        let mut resolver = UseResolver::new(&uses, &self.generics)?;
+        resolver.modify_item_trait_path(self);
+        Ok(())
+    }
+}
+
 impl Parse for ItemTraitPath {
     fn parse(input: ParseStream) -> syn::Result<Self> {
-        let outer_attrs = input.call(Attribute::parse_outer)?;
+        let mut outer_attrs = input.call(Attribute::parse_outer)?;
+        let mut uses = Vec::new();
+        while input.peek(Token![use]) {
+            uses.push(input.parse()?);
+        }
+        outer_attrs.extend(input.call(Attribute::parse_outer)?);
         let vis: Visibility = input.parse()?;
         let unsafety: Option<Token![unsafe]> = input.parse()?;
         let auto_token: Option<Token![auto]> = input.parse()?;
@@ -42,6 +76,7 @@ impl Parse for ItemTraitPath {
         let generics: Generics = parse2(arguments.to_token_stream())?;
         parse_rest_of_trait(
             input,
+            uses,
             outer_attrs,
             vis,
             unsafety,
@@ -53,8 +88,10 @@ impl Parse for ItemTraitPath {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn parse_rest_of_trait(
     input: ParseStream,
+    uses: Vec<ItemUse>,
     mut attrs: Vec<Attribute>,
     vis: Visibility,
     unsafety: Option<Token![unsafe]>,
@@ -90,6 +127,7 @@ fn parse_rest_of_trait(
     }
 
     Ok(ItemTraitPath {
+        uses,
         attrs,
         vis,
         unsafety,
@@ -124,6 +162,12 @@ fn single_parse_inner(input: ParseStream) -> syn::Result<Attribute> {
 
 impl ToTokens for ItemTraitPath {
     fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        tokens.append_all(&self.uses);
+        tokens.append_all(
+            self.attrs
+                .iter()
+                .filter(|attr| matches!(attr.style, AttrStyle::Outer)),
+        );
         self.vis.to_tokens(tokens);
         self.unsafety.to_tokens(tokens);
         self.auto_token.to_tokens(tokens);
@@ -131,14 +175,16 @@ impl ToTokens for ItemTraitPath {
         self.path.to_tokens(tokens);
         self.generics.to_tokens(tokens);
         if !self.supertraits.is_empty() {
-            self.colon_token
-                .clone()
-                .unwrap_or_default()
-                .to_tokens(tokens);
+            self.colon_token.unwrap_or_default().to_tokens(tokens);
             self.supertraits.to_tokens(tokens);
         }
         self.generics.where_clause.to_tokens(tokens);
         self.brace_token.surround(tokens, |tokens| {
+            tokens.append_all(
+                self.attrs
+                    .iter()
+                    .filter(|attr| !matches!(attr.style, AttrStyle::Outer)),
+            );
             tokens.append_all(&self.items);
         });
     }